@@ -0,0 +1,108 @@
+use crate::FactoryArgs;
+use soroban_sdk::{contractimpl, panic_with_error, token, Address, BytesN, Env};
+
+use crate::{
+    contract_registry::generate_salt,
+    errors::Errors,
+    storage::{
+        extend_instance_ttl, get_asset_farm, get_factory_admin, get_factory_count,
+        get_factory_wasm_hash, has_factory_admin, set_asset_farm, set_factory_admin,
+        set_factory_count, set_factory_wasm_hash, set_farm,
+    },
+    types::{AssetMode, FarmRecord},
+    Factory, FactoryClient, FactoryTrait,
+};
+
+#[contractimpl]
+impl FactoryTrait for Factory {
+    fn __constructor(env: Env, admin: Address, wasm_hash: BytesN<32>) {
+        admin.require_auth();
+
+        if has_factory_admin(&env) {
+            panic_with_error!(&env, &Errors::FactoryExists);
+        }
+
+        set_factory_admin(&env, &admin);
+        set_factory_wasm_hash(&env, &wasm_hash);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn upgrade(env: Env, hash: BytesN<32>) {
+        let admin = get_factory_admin(&env);
+
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(hash);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn set_wasm_hash(env: Env, wasm_hash: BytesN<32>) {
+        let admin = get_factory_admin(&env);
+
+        admin.require_auth();
+
+        // Only farms deployed from here on use it, existing farms upgrade themselves through their homesteader
+        set_factory_wasm_hash(&env, &wasm_hash);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn deploy(
+        env: Env,
+        homesteader: Address,
+        asset: Address,
+        interval: u64,
+        reward: i128,
+        mode: AssetMode,
+    ) -> Address {
+        let wasm_hash = get_factory_wasm_hash(&env);
+        let index = get_factory_count(&env);
+        let asset_client = token::StellarAssetClient::new(&env, &asset);
+
+        // Signers sign for `deploy` itself so simulation records the constructor and `set_admin` under it
+        homesteader.require_auth();
+
+        if mode == AssetMode::Mint {
+            asset_client.admin().require_auth();
+        }
+
+        // One farm per asset and homesteader, a `Mint` farm also needs the asset's admin to hand over
+        if get_asset_farm(&env, asset.clone(), homesteader.clone()).is_some() {
+            panic_with_error!(&env, &Errors::FarmExists);
+        }
+
+        let farm = env
+            .deployer()
+            .with_current_contract(generate_salt(&env, &asset, &homesteader))
+            .deploy_v2(
+                wasm_hash.clone(),
+                (homesteader.clone(), asset.clone(), interval, reward, mode),
+            );
+
+        // The same handoff as deploying KALE by hand, a `Mint` farm has to be the SAC admin to mint rewards
+        if mode == AssetMode::Mint {
+            asset_client.set_admin(&farm);
+        }
+
+        let record = FarmRecord {
+            index,
+            farm: farm.clone(),
+            homesteader: homesteader.clone(),
+            asset: asset.clone(),
+            interval,
+            reward,
+            mode,
+            wasm_hash,
+        };
+
+        set_farm(&env, &record);
+        set_asset_farm(&env, asset, homesteader, index);
+        set_factory_count(&env, index + 1);
+
+        extend_instance_ttl(&env);
+
+        farm
+    }
+}
@@ -0,0 +1,53 @@
+use crate::FactoryArgs;
+use soroban_sdk::{contractimpl, xdr::ToXdr, Address, BytesN, Env, Vec};
+
+use crate::{
+    storage::{get_asset_farm, get_factory_count, get_farm},
+    types::FarmRecord,
+    Factory, FactoryClient, RegistryTrait,
+};
+
+#[contractimpl]
+impl RegistryTrait for Factory {
+    // Where `deploy` puts the homesteader's farm for the asset, known before it exists so the SAC admin can sign for the handoff
+    fn farm_address(env: Env, asset: Address, homesteader: Address) -> Address {
+        env.deployer()
+            .with_current_contract(generate_salt(&env, &asset, &homesteader))
+            .deployed_address()
+    }
+
+    fn farm_count(env: Env) -> u32 {
+        get_factory_count(&env)
+    }
+
+    fn farm(env: Env, index: u32) -> Option<FarmRecord> {
+        get_farm(&env, index)
+    }
+
+    fn farm_by_asset(env: Env, asset: Address, homesteader: Address) -> Option<FarmRecord> {
+        get_asset_farm(&env, asset, homesteader).and_then(|index| get_farm(&env, index))
+    }
+
+    fn farms(env: Env, start: u32, limit: u32) -> Vec<FarmRecord> {
+        let end = get_factory_count(&env).min(start.saturating_add(limit));
+        let mut farms = Vec::new(&env);
+
+        for index in start..end {
+            if let Some(record) = get_farm(&env, index) {
+                farms.push_back(record);
+            }
+        }
+
+        farms
+    }
+}
+
+// Farms are salted by their asset and homesteader so each homesteader has one, predictable, farm address per asset
+// Nobody else can take that address, an `Escrow` farm needs no signature from the asset's admin
+pub fn generate_salt(env: &Env, asset: &Address, homesteader: &Address) -> BytesN<32> {
+    let mut bytes = asset.clone().to_xdr(env);
+
+    bytes.append(&homesteader.clone().to_xdr(env));
+
+    env.crypto().sha256(&bytes).to_bytes()
+}
@@ -0,0 +1,9 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Errors {
+    FactoryExists = 1,
+    FactoryMissing = 2,
+    FarmExists = 3,
+}
@@ -0,0 +1,45 @@
+#![no_std]
+
+use soroban_sdk::{contract, Address, BytesN, Env, Vec};
+use types::{AssetMode, FarmRecord};
+
+mod contract_factory;
+mod contract_registry;
+pub mod errors;
+mod storage;
+mod tests;
+pub mod types;
+
+pub const WEEK_OF_LEDGERS: u32 = 60 * 60 * 24 / 5 * 7; // assumes 5 second ledger close times
+
+#[contract]
+pub struct Factory;
+
+pub trait FactoryTrait {
+    fn __constructor(env: Env, admin: Address, wasm_hash: BytesN<32>);
+
+    fn upgrade(env: Env, hash: BytesN<32>);
+
+    fn set_wasm_hash(env: Env, wasm_hash: BytesN<32>);
+
+    fn deploy(
+        env: Env,
+        homesteader: Address,
+        asset: Address,
+        interval: u64,
+        reward: i128,
+        mode: AssetMode,
+    ) -> Address;
+}
+
+pub trait RegistryTrait {
+    fn farm_address(env: Env, asset: Address, homesteader: Address) -> Address;
+
+    fn farm_count(env: Env) -> u32;
+
+    fn farm(env: Env, index: u32) -> Option<FarmRecord>;
+
+    fn farm_by_asset(env: Env, asset: Address, homesteader: Address) -> Option<FarmRecord>;
+
+    fn farms(env: Env, start: u32, limit: u32) -> Vec<FarmRecord>;
+}
@@ -0,0 +1,83 @@
+use soroban_sdk::{panic_with_error, Address, BytesN, Env, IntoVal, Val};
+
+use crate::{
+    errors::Errors,
+    types::{FarmRecord, Storage},
+    WEEK_OF_LEDGERS,
+};
+
+pub fn extend_instance_ttl(env: &Env) {
+    let max_ttl = env.storage().max_ttl();
+
+    env.storage()
+        .instance()
+        .extend_ttl(max_ttl - WEEK_OF_LEDGERS, max_ttl);
+}
+
+pub fn has_factory_admin(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .has::<Storage>(&Storage::FactoryAdmin)
+}
+pub fn get_factory_admin(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get::<Storage, Address>(&Storage::FactoryAdmin)
+        .unwrap_or_else(|| panic_with_error!(&env, &Errors::FactoryMissing))
+}
+pub fn set_factory_admin(env: &Env, admin: &Address) {
+    env.storage()
+        .instance()
+        .set::<Storage, Address>(&Storage::FactoryAdmin, admin);
+}
+
+pub fn get_factory_wasm_hash(env: &Env) -> BytesN<32> {
+    env.storage()
+        .instance()
+        .get::<Storage, BytesN<32>>(&Storage::FactoryWasmHash)
+        .unwrap_or_else(|| panic_with_error!(&env, &Errors::FactoryMissing))
+}
+pub fn set_factory_wasm_hash(env: &Env, wasm_hash: &BytesN<32>) {
+    env.storage()
+        .instance()
+        .set::<Storage, BytesN<32>>(&Storage::FactoryWasmHash, wasm_hash);
+}
+
+pub fn get_factory_count(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get::<Storage, u32>(&Storage::FactoryCount)
+        .unwrap_or(0)
+}
+pub fn set_factory_count(env: &Env, count: u32) {
+    env.storage()
+        .instance()
+        .set::<Storage, u32>(&Storage::FactoryCount, &count);
+}
+
+pub fn get_farm(env: &Env, index: u32) -> Option<FarmRecord> {
+    env.storage()
+        .persistent()
+        .get::<Storage, FarmRecord>(&Storage::Farm(index))
+}
+pub fn set_farm(env: &Env, record: &FarmRecord) {
+    set_persistent_entry::<FarmRecord>(env, &Storage::Farm(record.index), record);
+}
+
+pub fn get_asset_farm(env: &Env, asset: Address, homesteader: Address) -> Option<u32> {
+    env.storage()
+        .persistent()
+        .get::<Storage, u32>(&Storage::AssetFarm(asset, homesteader))
+}
+pub fn set_asset_farm(env: &Env, asset: Address, homesteader: Address, index: u32) {
+    set_persistent_entry::<u32>(env, &Storage::AssetFarm(asset, homesteader), &index);
+}
+
+fn set_persistent_entry<V: IntoVal<Env, Val>>(env: &Env, key: &Storage, value: &V) {
+    let max_ttl = env.storage().max_ttl();
+
+    env.storage().persistent().set::<Storage, V>(key, value);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, max_ttl - WEEK_OF_LEDGERS, max_ttl);
+}
@@ -0,0 +1,183 @@
+#![cfg(test)]
+
+extern crate std;
+
+use kale_sc::{testutils::FarmHarness, ContractClient};
+use soroban_sdk::{
+    testutils::{Address as _, EnvTestConfig, Ledger},
+    token, Address, BytesN, Env,
+};
+
+use crate::{
+    errors::Errors,
+    tests::utils::farm_wasm_hash,
+    types::{AssetMode, FarmRecord},
+    Factory, FactoryClient,
+};
+
+#[test]
+fn test_deploy() {
+    let mut env = Env::default();
+
+    env.set_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+
+    env.ledger().set_min_temp_entry_ttl(17280);
+    env.ledger().set_min_persistent_entry_ttl(2073600);
+
+    env.mock_all_auths();
+
+    // The test host meters the whole test as one transaction, every call into the farm parses its wasm again
+    env.cost_estimate().budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let wasm_hash = farm_wasm_hash(&env);
+    let factory_address = env.register(Factory, (&admin, &wasm_hash));
+    let factory = FactoryClient::new(&env, &factory_address);
+
+    let issuer = Address::generate(&env);
+    let homesteader = Address::generate(&env);
+    let asset = env
+        .register_stellar_asset_contract_v2(issuer.clone())
+        .address();
+
+    let farm = factory.deploy(&homesteader, &asset, &60, &1_0000000, &AssetMode::Mint);
+
+    // The asset's admin signed the handoff and the homesteader the farm's constructor
+    let signers: std::vec::Vec<Address> = env
+        .auths()
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect();
+
+    assert!(signers.contains(&issuer));
+    assert!(signers.contains(&homesteader));
+
+    assert_eq!(farm, factory.farm_address(&asset, &homesteader));
+    assert_eq!(token::StellarAssetClient::new(&env, &asset).admin(), farm);
+
+    let record = FarmRecord {
+        index: 0,
+        farm: farm.clone(),
+        homesteader: homesteader.clone(),
+        asset: asset.clone(),
+        interval: 60,
+        reward: 1_0000000,
+        mode: AssetMode::Mint,
+        wasm_hash: wasm_hash.clone(),
+    };
+
+    assert_eq!(factory.farm_count(), 1);
+    assert_eq!(factory.farm(&0), Some(record.clone()));
+    assert_eq!(factory.farm(&1), None);
+    assert_eq!(
+        factory.farm_by_asset(&asset, &homesteader),
+        Some(record.clone())
+    );
+    assert_eq!(factory.farms(&0, &10).len(), 1);
+    assert_eq!(factory.farms(&1, &10).len(), 0);
+
+    // The deployed farm runs on its own interval and reward and can mint its asset
+    let harness = FarmHarness {
+        env: env.clone(),
+        homesteader: homesteader.clone(),
+        asset: asset.clone(),
+        farm: farm.clone(),
+        farm_client: ContractClient::new(&env, &farm),
+        asset_client: token::Client::new(&env, &asset),
+        interval: 60,
+    };
+    let farmer = harness.farmer(0);
+
+    harness.plant(&farmer, 0);
+    harness.work(&farmer, 4);
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    assert_eq!(harness.harvest(&farmer, 0), 1_0000000);
+    assert_eq!(harness.balance(&farmer), 1_0000000);
+
+    let err = factory
+        .try_deploy(&homesteader, &asset, &60, &1_0000000, &AssetMode::Mint)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::FarmExists.into());
+
+    // Bad parameters fail in the farm's constructor and leave nothing behind
+    let other_asset = env
+        .register_stellar_asset_contract_v2(issuer.clone())
+        .address();
+
+    assert!(factory
+        .try_deploy(&homesteader, &other_asset, &0, &1_0000000, &AssetMode::Mint)
+        .is_err());
+    assert_eq!(factory.farm_count(), 1);
+    assert_eq!(factory.farm_by_asset(&other_asset, &homesteader), None);
+
+    // An escrow farm leaves the asset's admin alone and doesn't need its signature
+    let escrow_farm = factory.deploy(
+        &homesteader,
+        &other_asset,
+        &60,
+        &1_0000000,
+        &AssetMode::Escrow,
+    );
+    let signers: std::vec::Vec<Address> = env
+        .auths()
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect();
+
+    assert!(!signers.contains(&issuer));
+    assert_eq!(
+        token::StellarAssetClient::new(&env, &other_asset).admin(),
+        issuer
+    );
+    assert_eq!(factory.farm_count(), 2);
+    assert_eq!(
+        factory
+            .farm_by_asset(&other_asset, &homesteader)
+            .unwrap()
+            .farm,
+        escrow_farm
+    );
+    assert_eq!(factory.farms(&0, &10).len(), 2);
+
+    // Anyone can deploy an escrow farm for an asset, but only at their own address, so getting in first takes nobody's farm
+    let squatter = Address::generate(&env);
+    let third_asset = env
+        .register_stellar_asset_contract_v2(issuer.clone())
+        .address();
+    let squatter_farm =
+        factory.deploy(&squatter, &third_asset, &60, &1_0000000, &AssetMode::Escrow);
+    let third_farm = factory.deploy(
+        &homesteader,
+        &third_asset,
+        &60,
+        &1_0000000,
+        &AssetMode::Escrow,
+    );
+
+    assert_ne!(squatter_farm, third_farm);
+    assert_eq!(
+        factory.farm_by_asset(&third_asset, &squatter).unwrap().farm,
+        squatter_farm
+    );
+    assert_eq!(
+        factory
+            .farm_by_asset(&third_asset, &homesteader)
+            .unwrap()
+            .farm,
+        third_farm
+    );
+    assert_eq!(factory.farm_count(), 4);
+
+    // New wasm only applies to farms deployed after the switch
+    let new_wasm_hash = BytesN::from_array(&env, &[1; 32]);
+
+    factory.set_wasm_hash(&new_wasm_hash);
+
+    assert_eq!(factory.farm(&0), Some(record));
+}
@@ -0,0 +1,12 @@
+#![cfg(test)]
+
+use soroban_sdk::{Bytes, BytesN, Env};
+
+// The farm contract as deployed, built for `wasm32v1-none` since the host rejects newer wasm features
+// `make farm-wasm` rebuilds it after changes to kale-sc
+const FARM_WASM: &[u8] = include_bytes!("kale_sc.wasm");
+
+pub fn farm_wasm_hash(env: &Env) -> BytesN<32> {
+    env.deployer()
+        .upload_contract_wasm(Bytes::from_slice(env, FARM_WASM))
+}
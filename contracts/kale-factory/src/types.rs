@@ -0,0 +1,33 @@
+use soroban_sdk::{contracttype, Address, BytesN};
+
+// Mirrors the farm's `AssetMode`, `Mint` farms get SAC admin handed over at deploy and `Escrow` farms don't
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssetMode {
+    Mint,
+    Escrow,
+}
+
+// A farm the factory deployed, `interval` and `reward` are what it was constructed with
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FarmRecord {
+    pub index: u32,
+    pub farm: Address,
+    pub homesteader: Address,
+    pub asset: Address,
+    pub interval: u64,
+    pub reward: i128,
+    pub mode: AssetMode,
+    pub wasm_hash: BytesN<32>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Storage {
+    FactoryAdmin,                // : address
+    FactoryWasmHash,             // : BytesN<32>
+    FactoryCount,                // : u32
+    Farm(u32),                   // (index) : FarmRecord (persistent)
+    AssetFarm(Address, Address), // (asset, homesteader) : u32 (persistent)
+}
@@ -1,16 +1,28 @@
 use crate::ContractArgs;
-use soroban_fixed_point_math::SorobanFixedPoint;
-use soroban_sdk::{contractimpl, panic_with_error, token, xdr::ToXdr, Address, Bytes, BytesN, Env};
+use soroban_sdk::{
+    contractimpl, panic_with_error, symbol_short, token, xdr::ToXdr, Address, Bytes, BytesN, Env,
+    Vec,
+};
 
 use crate::{
+    contract_season::record_contribution,
     errors::Errors,
     storage::{
-        bump_farm_index, extend_instance_ttl, get_block, get_farm_asset, get_farm_block,
-        get_farm_index, get_farm_paused, get_pail, has_pail, remove_pail, set_block,
-        set_farm_block, set_pail,
+        bump_farm_index, extend_instance_ttl, get_block, get_block_summary, get_farm_algorithm,
+        get_farm_asset, get_farm_block, get_farm_escrow, get_farm_fee, get_farm_greenhoused,
+        get_farm_index, get_farm_interval, get_farm_mode, get_farm_normalization, get_farm_paused,
+        get_farm_refunds, get_farm_reward, get_farm_sunset, get_farm_supply, get_farm_vested,
+        get_farm_vesting, get_greenhouse, get_greenhouse_pail, get_pail, get_vesting, has_pail,
+        is_blocked, remove_greenhouse, remove_greenhouse_pail, remove_pail, remove_vesting,
+        set_block, set_block_summary, set_farm_block, set_farm_escrow, set_farm_greenhoused,
+        set_farm_sunset, set_farm_supply, set_farm_vested, set_greenhouse, set_greenhouse_pail,
+        set_pail, set_vesting,
+    },
+    types::{
+        AssetMode, Block, BlockSummary, Difficulty, FeeSchedule, Greenhouse, HashAlgorithm,
+        Normalization, Pail, Reward, Sunset, SupplyStats, Vesting,
     },
-    types::{Block, Pail},
-    Contract, ContractClient, FarmTrait, BLOCK_INTERVAL, BLOCK_REWARD,
+    Contract, ContractClient, FarmTrait,
 };
 
 #[contractimpl]
@@ -18,80 +30,16 @@ impl FarmTrait for Contract {
     fn plant(env: Env, farmer: Address, amount: i128) {
         farmer.require_auth();
 
-        let asset = get_farm_asset(&env);
-        let mut index = get_farm_index(&env);
-        let mut farm_block = get_farm_block(&env).unwrap_or(new_farm_block(&env));
-        let paused = get_farm_paused(&env);
-        let mut block = match get_block(&env, index) {
-            // genesis or evicted
-            None => {
-                if index > 0 {
-                    // Only when we're in an evicted scenario should the index be bumped
-                    bump_farm_index(&env, &mut index);
-                }
-
-                new_block(&env, &farm_block)
-            }
-            Some(block) => {
-                // if the block is >= BLOCK_INTERVAL old, we need to create a new one
-                if env.ledger().timestamp() >= block.timestamp + BLOCK_INTERVAL {
-                    let block = new_block(&env, &farm_block);
-
-                    // ensure we put this after the `new_block` above
-                    farm_block = new_farm_block(&env);
-                    bump_farm_index(&env, &mut index);
-
-                    block
-                } else {
-                    block
-                }
-            }
-        };
-
-        if paused {
-            panic_with_error!(&env, &Errors::FarmPaused);
-        }
+        let (index, block, farm_block) = discover_block(&env);
 
-        if amount < 0 {
-            panic_with_error!(&env, &Errors::PlantAmountTooLow);
-        }
-
-        // must come after block discovery as the index may have been bumped
-        if has_pail(&env, farmer.clone(), index) {
-            panic_with_error!(&env, &Errors::PailExists);
-        }
-
-        block.staked_total += amount;
-
-        if amount > 0 {
-            token::Client::new(&env, &asset).burn(&farmer, &amount);
-        }
-
-        if amount > farm_block.max_stake {
-            farm_block.max_stake = amount;
-        }
-
-        if amount < farm_block.min_stake {
-            farm_block.min_stake = amount;
-        }
-
-        let pail = Pail {
-            sequence: env.ledger().sequence(),
-            gap: None,
-            stake: amount,
-            zeros: None,
-        };
-
-        set_pail(&env, farmer, index, pail);
-        set_block(&env, index, &block);
-        set_farm_block(&env, &farm_block);
-
-        extend_instance_ttl(&env);
+        plant_pail(&env, farmer, index, block, farm_block, amount, true);
     }
 
     fn work(env: Env, farmer: Address, hash: BytesN<32>, nonce: u64) -> u32 {
         // No auth_require here so others can call this function on the `farmer`'s behalf
 
+        check_farmer(&env, &farmer);
+
         let index = get_farm_index(&env);
         let mut farm_block = get_farm_block(&env)
             .unwrap_or_else(|| panic_with_error!(&env, &Errors::HomesteadMissing));
@@ -100,26 +48,30 @@ impl FarmTrait for Contract {
         let mut pail = get_pail(&env, farmer.clone(), index)
             .unwrap_or_else(|| panic_with_error!(&env, &Errors::PailMissing));
 
-        let generated_hash = generate_hash(&env, &index, &nonce, &block.entropy, &farmer);
+        let generated_hash = generate_hash(
+            &env,
+            &block.algorithm,
+            &index,
+            &nonce,
+            &block.entropy,
+            &farmer,
+        );
         let sequence = env.ledger().sequence();
         let gap = sequence - pail.sequence;
-        let mut zeros = 0;
 
         if hash != generated_hash {
             panic_with_error!(&env, &Errors::HashInvalid);
         }
 
-        for byte in hash {
-            if byte == 0 {
-                zeros += 2;
-            } else {
-                zeros += byte.leading_zeros() / 4;
-                break;
-            }
-        }
-
-        let (normalized_gap, normalized_stake, normalized_zeros) =
-            generate_normalizations(&env, &block, gap, pail.stake, zeros);
+        let zeros = kale_core::count_zeros(&hash.to_array(), block.difficulty.into());
+        let (normalized_gap, normalized_stake, normalized_zeros) = generate_normalizations(
+            &env,
+            &(&block).into(),
+            &block.normalization,
+            gap,
+            pail.stake,
+            zeros,
+        );
 
         block.normalized_total += normalized_gap + normalized_stake + normalized_zeros;
 
@@ -130,7 +82,14 @@ impl FarmTrait for Contract {
                 }
 
                 let (prev_normalized_gap, prev_normalized_stake, prev_normalized_zeros) =
-                    generate_normalizations(&env, &block, gap, pail.stake, prev_zeros);
+                    generate_normalizations(
+                        &env,
+                        &(&block).into(),
+                        &block.normalization,
+                        gap,
+                        pail.stake,
+                        prev_zeros,
+                    );
 
                 block.normalized_total -=
                     prev_normalized_gap + prev_normalized_stake + prev_normalized_zeros;
@@ -141,6 +100,8 @@ impl FarmTrait for Contract {
         }
 
         farm_block.entropy = generated_hash;
+        // the bounds below are on this block's scale, which the next block may not share
+        farm_block.difficulty = block.difficulty;
 
         if gap > farm_block.max_gap {
             farm_block.max_gap = gap;
@@ -171,44 +132,822 @@ impl FarmTrait for Contract {
     }
 
     fn harvest(env: Env, farmer: Address, index: u32) -> i128 {
-        let asset = get_farm_asset(&env);
+        let (reward, liquid, stake) = harvest_pail(&env, &farmer, index);
+
+        pay_harvest(&env, &farmer, stake, liquid);
+
+        extend_instance_ttl(&env);
+
+        reward
+    }
+
+    fn harvest_and_plant(env: Env, farmer: Address, harvest_index: u32, restake_bps: u32) -> i128 {
+        farmer.require_auth();
+
+        if restake_bps > 10_000 {
+            panic_with_error!(&env, &Errors::RestakeInvalid);
+        }
+
+        // discover first so the first farmer into a new block can harvest the one it closes
+        let (index, block, farm_block) = discover_block(&env);
+        let (reward, liquid, stake) = harvest_pail(&env, &farmer, harvest_index);
+        let restake = liquid * restake_bps as i128 / 10_000;
+
+        pay_harvest(&env, &farmer, stake, liquid - restake);
+
+        // the restaked portion was never paid out so there's nothing to collect
+        plant_pail(&env, farmer, index, block, farm_block, restake, false);
+
+        reward
+    }
+
+    fn greenhouse(env: Env, farmer: Address, index: u32) {
+        // The farmer's transaction pays the persistent rent
+        farmer.require_auth();
+
+        check_farmer(&env, &farmer);
+
         let farm_index = get_farm_index(&env);
-        let block = get_block(&env, index)
-            .unwrap_or_else(|| panic_with_error!(&env, &Errors::BlockMissing));
-        let Pail {
-            gap, stake, zeros, ..
-        } = get_pail(&env, farmer.clone(), index)
+        let pail = get_pail(&env, farmer.clone(), index)
             .unwrap_or_else(|| panic_with_error!(&env, &Errors::PailMissing));
 
+        // Only a closed block's totals are final
         if index >= farm_index {
             panic_with_error!(&env, &Errors::HarvestNotReady);
         }
 
+        if pail.gap.is_none() || pail.zeros.is_none() {
+            panic_with_error!(&env, &Errors::WorkMissing);
+        }
+
+        let greenhouse = match get_greenhouse(&env, index) {
+            Some(greenhouse) => Greenhouse {
+                pails: greenhouse.pails + 1,
+                ..greenhouse
+            },
+            None => Greenhouse {
+                summary: find_summary(&env, index),
+                pails: 1,
+            },
+        };
+
+        set_greenhouse(&env, index, &greenhouse);
+        set_greenhouse_pail(&env, farmer.clone(), index, pail);
+        set_farm_greenhoused(&env, get_farm_greenhoused(&env) + 1);
+        remove_pail(&env, farmer, index);
+        release_pail(&env, index);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn estimate_reward(env: Env, farmer: Address, index: u32) -> Reward {
+        let summary = find_summary(&env, index);
+        let (
+            Pail {
+                gap, stake, zeros, ..
+            },
+            _,
+        ) = find_pail(&env, &farmer, index);
+
         if gap.is_none() || zeros.is_none() {
             panic_with_error!(&env, &Errors::WorkMissing);
         }
 
-        let gap = gap.unwrap();
-        let zeros = zeros.unwrap();
+        // While the block is open this is a projection, other farmers' work will still move `normalized_total`
+        generate_reward(&env, &summary, gap.unwrap(), stake, zeros.unwrap())
+    }
+
+    fn preview_reward(
+        env: Env,
+        index: u32,
+        farmer: Option<Address>,
+        stake: i128,
+        gap: u32,
+        zeros: u32,
+    ) -> Reward {
+        let mut summary = find_summary(&env, index);
 
-        let (normalized_gap, normalized_stake, normalized_zeros) =
-            generate_normalizations(&env, &block, gap, stake, zeros);
+        // A farmer resubmitting replaces their current contribution, the way `work` does with `prev_zeros`
+        if let Some(pail) = farmer.and_then(|farmer| get_pail(&env, farmer, index)) {
+            match pail.zeros {
+                Some(prev_zeros) => {
+                    let (prev_normalized_gap, prev_normalized_stake, prev_normalized_zeros) =
+                        generate_normalizations(
+                            &env,
+                            &(&summary).into(),
+                            &summary.normalization,
+                            gap,
+                            pail.stake,
+                            prev_zeros,
+                        );
 
-        let reward = (normalized_gap + normalized_stake + normalized_zeros).fixed_mul_floor(
+                    summary.normalized_total -=
+                        prev_normalized_gap + prev_normalized_stake + prev_normalized_zeros;
+                }
+                // `work` takes an unworked stake back out of the pool
+                None => summary.reward_pool -= pail.stake,
+            }
+        }
+
+        let (normalized_gap, normalized_stake, normalized_zeros) = generate_normalizations(
             &env,
-            &(BLOCK_REWARD + block.staked_total),
-            &block.normalized_total.max(1),
-        ) + stake;
+            &(&summary).into(),
+            &summary.normalization,
+            gap,
+            stake,
+            zeros,
+        );
+
+        // Apply the contribution the way `work` would, a new farmer's `plant` and `work` cancel out on `staked_total`
+        summary.normalized_total += normalized_gap + normalized_stake + normalized_zeros;
+
+        generate_reward(&env, &summary, gap, stake, zeros)
+    }
+
+    fn block_summary(env: Env, index: u32) -> Option<BlockSummary> {
+        get_block_summary(&env, index)
+    }
+
+    fn claim_vested(env: Env, farmer: Address) -> i128 {
+        // No auth_require here, like `harvest` the claim can only ever pay the `farmer`
+
+        check_farmer(&env, &farmer);
 
-        if reward > 0 {
-            token::StellarAssetClient::new(&env, &asset).mint(&farmer, &reward);
+        let mut vesting = get_vesting(&env, farmer.clone())
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::VestingMissing));
+
+        release_vesting(&env, &mut vesting);
+
+        // An `Escrow` farm pays what its treasury can cover and keeps the rest unlocked for later
+        let claimed = match get_farm_mode(&env) {
+            AssetMode::Mint => vesting.unlocked,
+            AssetMode::Escrow => vesting.unlocked.min(get_treasury(&env).max(0)),
+        };
+
+        vesting.unlocked -= claimed;
+
+        if vesting.locked == 0 && vesting.unlocked == 0 {
+            remove_vesting(&env, farmer.clone());
+        } else {
+            set_vesting(&env, farmer.clone(), &vesting);
         }
 
+        if claimed > 0 {
+            let mut supply = get_farm_supply(&env);
+
+            supply.minted_reward += claimed;
+
+            set_farm_supply(&env, &supply);
+            set_farm_vested(&env, get_farm_vested(&env) - claimed);
+        }
+
+        pay_reward(&env, &farmer, claimed);
+
+        extend_instance_ttl(&env);
+
+        claimed
+    }
+
+    fn vesting(env: Env, farmer: Address) -> Option<Vesting> {
+        get_vesting(&env, farmer).map(|mut vesting| {
+            release_vesting(&env, &mut vesting);
+            vesting
+        })
+    }
+
+    fn treasury(env: Env) -> i128 {
+        get_treasury(&env)
+    }
+
+    fn fees(env: Env) -> i128 {
+        get_farm_supply(&env).fees
+    }
+
+    fn supply_stats(env: Env) -> SupplyStats {
+        get_farm_supply(&env)
+    }
+
+    fn blocked(env: Env, farmer: Address) -> bool {
+        is_blocked(&env, farmer)
+    }
+
+    fn refund(env: Env, farmer: Address, index: u32) -> i128 {
+        // No auth_require here, like `harvest` the refund can only ever pay the `farmer`
+
+        check_farmer(&env, &farmer);
+
+        // Only the open block's stakes are still held apart, a closed block's unworked ones are already in its reward pool
+        if !get_farm_refunds(&env).contains(index) || index != get_farm_index(&env) {
+            panic_with_error!(&env, &Errors::RefundNotAllowed);
+        }
+
+        let mut block = get_block(&env, index)
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::BlockMissing));
+        let pail = get_pail(&env, farmer.clone(), index)
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::PailMissing));
+
+        // Take the pail out as if it had never been planted
+        if !withdraw_work(&env, &mut block, &pail) {
+            block.staked_total -= pail.stake;
+        }
+
+        block.pails = block.pails.saturating_sub(1);
+
         remove_pail(&env, farmer.clone(), index);
+        set_block(&env, index, &block);
+
+        if pail.stake > 0 {
+            if get_farm_mode(&env) == AssetMode::Escrow {
+                set_farm_escrow(&env, get_farm_escrow(&env) - pail.stake);
+            }
+
+            let mut supply = get_farm_supply(&env);
+
+            supply.minted_stake += pail.stake;
+
+            set_farm_supply(&env, &supply);
+            pay_reward(&env, &farmer, pail.stake);
+        }
+
+        env.events()
+            .publish((symbol_short!("refund"), farmer), (index, pail.stake));
 
         extend_instance_ttl(&env);
 
-        reward
+        pail.stake
+    }
+
+    fn refunds(env: Env) -> Vec<u32> {
+        get_farm_refunds(&env)
+    }
+
+    fn sunset(env: Env) -> Option<Sunset> {
+        get_farm_sunset(&env)
+    }
+}
+
+// The current block and farm block, closing the current block and opening the next if it's due
+fn discover_block(env: &Env) -> (u32, Block, Block) {
+    let mut index = get_farm_index(env);
+    let mut farm_block = get_farm_block(env).unwrap_or(new_farm_block(env));
+    let block = match get_block(env, index) {
+        // genesis or evicted
+        None => {
+            if index > 0 {
+                // Only when we're in an evicted scenario should the index be bumped
+                bump_farm_index(env, &mut index);
+                release_escrow(env);
+            }
+
+            new_block(env, index, &farm_block)
+        }
+        Some(block) => {
+            // if the block is >= the farm's interval old, we need to create a new one
+            if is_block_due(env, &block) {
+                close_block(env, index, block);
+
+                let block = new_block(env, index + 1, &farm_block);
+
+                // ensure we put this after the `new_block` above
+                farm_block = new_farm_block(env);
+                bump_farm_index(env, &mut index);
+
+                block
+            } else {
+                block
+            }
+        }
+    };
+
+    (index, block, farm_block)
+}
+
+fn is_block_due(env: &Env, block: &Block) -> bool {
+    env.ledger().timestamp() >= block.timestamp + get_farm_interval(env)
+}
+
+// Settles a block that's done, opening the next one is up to the caller
+fn close_block(env: &Env, index: u32, mut block: Block) {
+    // unworked stakes are now part of the reward pool, paid out like the treasury
+    if block.staked_total > 0 {
+        let mut supply = get_farm_supply(env);
+
+        supply.forfeited += block.staked_total;
+
+        set_farm_supply(env, &supply);
+    }
+
+    release_escrow(env);
+
+    // what was actually paid, a later change to the fee schedule doesn't reach back into a closed block
+    block.fee = Some(pay_fee(env, index));
+
+    // the closing block is final, keep what `harvest` needs past its temporary entry
+    set_block_summary(env, &summarize_block(env, index, &block));
+    set_block(env, index, &block);
+}
+
+// Nothing opens the block after a sunsetting farm's final one, so it's closed here once it's due
+// An evicted final block has nothing left to harvest and counts as closed
+// Returns whether the final block is closed, which starts the harvest window
+pub(crate) fn close_final_block(env: &Env, sunset: &mut Sunset) -> bool {
+    if sunset.closed.is_some() {
+        return true;
+    }
+
+    let mut index = get_farm_index(env);
+
+    if index < sunset.final_index {
+        return false;
+    }
+
+    if let Some(block) = get_block(env, index) {
+        if !is_block_due(env, &block) {
+            return false;
+        }
+
+        close_block(env, index, block);
+    }
+
+    bump_farm_index(env, &mut index);
+    release_escrow(env);
+
+    sunset.closed = Some(env.ledger().sequence());
+
+    set_farm_sunset(env, sunset);
+
+    true
+}
+
+// Stakes `amount` into the discovered block, collecting it from the farmer unless it never left the farm
+fn plant_pail(
+    env: &Env,
+    farmer: Address,
+    index: u32,
+    mut block: Block,
+    mut farm_block: Block,
+    amount: i128,
+    collect: bool,
+) {
+    let paused = get_farm_paused(env);
+
+    if paused {
+        panic_with_error!(env, &Errors::FarmPaused);
+    }
+
+    check_farmer(env, &farmer);
+
+    // must come after block discovery, which may have just closed the final block
+    if get_farm_sunset(env).is_some_and(|sunset| index > sunset.final_index) {
+        panic_with_error!(env, &Errors::FarmSunset);
+    }
+
+    if amount < 0 {
+        panic_with_error!(env, &Errors::PlantAmountTooLow);
+    }
+
+    // must come after block discovery as the index may have been bumped
+    if has_pail(env, farmer.clone(), index) {
+        panic_with_error!(env, &Errors::PailExists);
+    }
+
+    block.staked_total += amount;
+    block.pails = block.pails.saturating_add(1);
+
+    if collect && amount > 0 {
+        let mut supply = get_farm_supply(env);
+
+        supply.burned += amount;
+
+        set_farm_supply(env, &supply);
+        collect_stake(env, &farmer, amount);
+    }
+
+    // held until the block closes, from then on its stakes are paid back out of the treasury
+    if amount > 0 && get_farm_mode(env) == AssetMode::Escrow {
+        set_farm_escrow(env, get_farm_escrow(env) + amount);
+    }
+
+    if amount > farm_block.max_stake {
+        farm_block.max_stake = amount;
+    }
+
+    if amount < farm_block.min_stake {
+        farm_block.min_stake = amount;
+    }
+
+    let pail = Pail {
+        sequence: env.ledger().sequence(),
+        gap: None,
+        stake: amount,
+        zeros: None,
+    };
+
+    set_pail(env, farmer, index, pail);
+    set_block(env, index, &block);
+    set_farm_block(env, &farm_block);
+
+    extend_instance_ttl(env);
+}
+
+// Works out the farmer's reward for a closed block and empties their pail, minting is left to the caller
+// Returns the full reward, the liquid part of it and the pail's stake, whatever isn't liquid has been locked into the farmer's vesting
+fn harvest_pail(env: &Env, farmer: &Address, index: u32) -> (i128, i128, i128) {
+    check_farmer(env, farmer);
+
+    if let Some(mut sunset) = get_farm_sunset(env) {
+        close_final_block(env, &mut sunset);
+    }
+
+    let farm_index = get_farm_index(env);
+    let summary = find_summary(env, index);
+    let (
+        Pail {
+            gap, stake, zeros, ..
+        },
+        greenhoused,
+    ) = find_pail(env, farmer, index);
+
+    if index >= farm_index {
+        panic_with_error!(env, &Errors::HarvestNotReady);
+    }
+
+    if gap.is_none() || zeros.is_none() {
+        panic_with_error!(env, &Errors::WorkMissing);
+    }
+
+    let Reward { reward, .. } = generate_reward(env, &summary, gap.unwrap(), stake, zeros.unwrap());
+
+    // The returned stake was never earned
+    record_contribution(env, farmer, index, reward - stake);
+
+    if greenhoused {
+        remove_greenhoused(env, farmer, index);
+    } else {
+        remove_pail(env, farmer.clone(), index);
+        release_pail(env, index);
+    }
+
+    let locked = vest_reward(env, farmer, reward, stake);
+    let mut liquid = reward - locked;
+
+    if get_farm_mode(env) == AssetMode::Escrow {
+        // The returned stake was released into the treasury with its block, so it's paid alongside the earnings
+        // A low treasury doesn't fail the harvest, the shortfall is left unlocked for `claim_vested`
+        let treasury = get_treasury(env).max(0);
+
+        if liquid > treasury {
+            defer_reward(env, farmer, liquid - treasury);
+            liquid = treasury;
+        }
+    }
+
+    (reward, liquid, stake)
+}
+
+// Takes the farmer's pail out of the block's greenhouse, the last one out takes the block copy with it
+fn remove_greenhoused(env: &Env, farmer: &Address, index: u32) {
+    remove_greenhouse_pail(env, farmer.clone(), index);
+    set_farm_greenhoused(env, get_farm_greenhoused(env).saturating_sub(1));
+
+    if let Some(mut greenhouse) = get_greenhouse(env, index) {
+        greenhouse.pails -= 1;
+
+        if greenhouse.pails == 0 {
+            remove_greenhouse(env, index);
+        } else {
+            set_greenhouse(env, index, &greenhouse);
+        }
+    }
+}
+
+// Drops the farmer's greenhoused pail for `index` unharvested, its reward is never paid
+pub(crate) fn forfeit_greenhoused(env: &Env, farmer: &Address, index: u32) {
+    if get_greenhouse_pail(env, farmer.clone(), index).is_none() {
+        panic_with_error!(env, &Errors::PailMissing);
+    }
+
+    remove_greenhoused(env, farmer, index);
+}
+
+// Drops the farmer's vesting, locked and unlocked alike
+// Returns the forfeited amount
+pub(crate) fn forfeit_vesting(env: &Env, farmer: &Address) -> i128 {
+    let Some(vesting) = get_vesting(env, farmer.clone()) else {
+        return 0;
+    };
+    let forfeited = vesting.locked + vesting.unlocked;
+
+    remove_vesting(env, farmer.clone());
+    set_farm_vested(env, get_farm_vested(env) - forfeited);
+
+    forfeited
+}
+
+// Empties the farmer's pail in the current block, leaving its stake and share of the pool to the block's other farmers
+// Returns the block's index and the forfeited stake, if there was a pail
+pub(crate) fn forfeit_pail(env: &Env, farmer: &Address) -> Option<(u32, i128)> {
+    let index = get_farm_index(env);
+    let mut block = get_block(env, index)?;
+    let pail = get_pail(env, farmer.clone(), index)?;
+
+    // an unworked stake is still in `staked_total`, a worked one has to be handed back along with its contribution
+    if withdraw_work(env, &mut block, &pail) {
+        block.staked_total += pail.stake;
+    }
+
+    block.pails = block.pails.saturating_sub(1);
+
+    remove_pail(env, farmer.clone(), index);
+    set_block(env, index, &block);
+
+    Some((index, pail.stake))
+}
+
+// Takes a worked pail's contribution back out of the block's `normalized_total`, returning whether it had one
+fn withdraw_work(env: &Env, block: &mut Block, pail: &Pail) -> bool {
+    let (Some(gap), Some(zeros)) = (pail.gap, pail.zeros) else {
+        return false;
+    };
+
+    let (normalized_gap, normalized_stake, normalized_zeros) = generate_normalizations(
+        env,
+        &(&*block).into(),
+        &block.normalization,
+        gap,
+        pail.stake,
+        zeros,
+    );
+
+    block.normalized_total -= normalized_gap + normalized_stake + normalized_zeros;
+
+    true
+}
+
+// A pail leaving a closed block no longer holds it back from `remove_block`
+fn release_pail(env: &Env, index: u32) {
+    if let Some(mut block) = get_block(env, index) {
+        block.pails = block.pails.saturating_sub(1);
+
+        set_block(env, index, &block);
+    }
+}
+
+pub(crate) fn check_farmer(env: &Env, farmer: &Address) {
+    check_locked(env);
+
+    if is_blocked(env, farmer.clone()) {
+        panic_with_error!(env, &Errors::FarmerBlocked);
+    }
+}
+
+// Pays a season bonus as earnings, an `Escrow` farm short on treasury leaves the rest unlocked for `claim_vested`
+pub(crate) fn pay_bonus(env: &Env, farmer: &Address, amount: i128) {
+    let mut liquid = amount;
+
+    if get_farm_mode(env) == AssetMode::Escrow {
+        let treasury = get_treasury(env).max(0);
+
+        if liquid > treasury {
+            defer_reward(env, farmer, liquid - treasury);
+            liquid = treasury;
+        }
+    }
+
+    pay_harvest(env, farmer, 0, liquid);
+}
+
+pub(crate) fn check_locked(env: &Env) {
+    if get_farm_sunset(env).is_some_and(|sunset| sunset.complete) {
+        panic_with_error!(env, &Errors::FarmLocked);
+    }
+}
+
+// Pays out `amount` of a harvest, counting it as returned stake first and earnings after
+fn pay_harvest(env: &Env, farmer: &Address, stake: i128, amount: i128) {
+    if amount <= 0 {
+        return;
+    }
+
+    let mut supply = get_farm_supply(env);
+    let minted_stake = amount.min(stake);
+
+    supply.minted_stake += minted_stake;
+    supply.minted_reward += amount - minted_stake;
+
+    set_farm_supply(env, &supply);
+    pay_reward(env, farmer, amount);
+}
+
+// The fee share of the block reward, never of the stakes in the pool
+fn generate_fee(env: &Env) -> i128 {
+    match get_farm_fee(env) {
+        Some(fee) => get_farm_reward(env) * fee.bps as i128 / 10_000,
+        None => 0,
+    }
+}
+
+// Pays the closing block's fee to the fee recipient, `Escrow` farms only as far as the treasury covers it
+// Returns the fee paid
+fn pay_fee(env: &Env, index: u32) -> i128 {
+    let Some(FeeSchedule { recipient, .. }) = get_farm_fee(env) else {
+        return 0;
+    };
+
+    let mut fee = generate_fee(env);
+
+    if get_farm_mode(env) == AssetMode::Escrow {
+        fee = fee.min(get_treasury(env).max(0));
+    }
+
+    if fee == 0 {
+        return 0;
+    }
+
+    let mut supply = get_farm_supply(env);
+
+    supply.fees += fee;
+
+    set_farm_supply(env, &supply);
+    pay_reward(env, &recipient, fee);
+
+    env.events()
+        .publish((symbol_short!("fee"), recipient), (index, fee));
+
+    fee
+}
+
+// Burns the farmer's stake, or for `Escrow` farms transfers it into the farm's balance
+fn collect_stake(env: &Env, farmer: &Address, amount: i128) {
+    let asset = get_farm_asset(env);
+
+    match get_farm_mode(env) {
+        AssetMode::Mint => token::Client::new(env, &asset).burn(farmer, &amount),
+        AssetMode::Escrow => token::Client::new(env, &asset).transfer(
+            farmer,
+            &env.current_contract_address(),
+            &amount,
+        ),
+    }
+}
+
+// Mints `amount` to the farmer, or for `Escrow` farms transfers it out of the farm's balance
+fn pay_reward(env: &Env, farmer: &Address, amount: i128) {
+    if amount <= 0 {
+        return;
+    }
+
+    let asset = get_farm_asset(env);
+
+    match get_farm_mode(env) {
+        AssetMode::Mint => token::StellarAssetClient::new(env, &asset).mint(farmer, &amount),
+        AssetMode::Escrow => token::Client::new(env, &asset).transfer(
+            &env.current_contract_address(),
+            farmer,
+            &amount,
+        ),
+    }
+}
+
+// Escrow only ever holds the open block's stakes, a closed or evicted block's are handed to the treasury
+// That way a pail that's never harvested, or a block that's never closed, can't keep its stake out of the treasury
+fn release_escrow(env: &Env) {
+    if get_farm_escrow(env) != 0 {
+        set_farm_escrow(env, 0);
+    }
+}
+
+// Whatever the farm holds beyond the stakes in escrow
+fn get_treasury(env: &Env) -> i128 {
+    let asset = get_farm_asset(env);
+
+    token::Client::new(env, &asset).balance(&env.current_contract_address()) - get_farm_escrow(env)
+}
+
+// Owes the farmer `amount` through their vesting's unlocked balance
+fn defer_reward(env: &Env, farmer: &Address, amount: i128) {
+    let sequence = env.ledger().sequence();
+    let mut vesting = get_vesting(env, farmer.clone()).unwrap_or(Vesting {
+        unlocked: 0,
+        locked: 0,
+        start: sequence,
+        end: sequence,
+    });
+
+    vesting.unlocked += amount;
+
+    set_vesting(env, farmer.clone(), &vesting);
+    set_farm_vested(env, get_farm_vested(env) + amount);
+}
+
+// Locks the homestead's share of the reward's earnings into the farmer's vesting, the returned stake stays liquid
+// Anything still locked from earlier harvests is merged in, the schedule ends at the locked amounts' weighted end
+fn vest_reward(env: &Env, farmer: &Address, reward: i128, stake: i128) -> i128 {
+    let schedule = get_farm_vesting(env);
+    let locked = (reward - stake).max(0) * schedule.bps as i128 / 10_000;
+
+    if locked == 0 {
+        return 0;
+    }
+
+    let sequence = env.ledger().sequence();
+    let mut vesting = get_vesting(env, farmer.clone()).unwrap_or(Vesting {
+        unlocked: 0,
+        locked: 0,
+        start: sequence,
+        end: sequence,
+    });
+
+    release_vesting(env, &mut vesting);
+
+    // Older tranches keep roughly their pace rather than restarting at the full `ledgers`
+    let remaining = vesting.end.saturating_sub(sequence) as i128;
+    let ledgers = vesting
+        .locked
+        .checked_mul(remaining)
+        .zip(locked.checked_mul(schedule.ledgers as i128))
+        .and_then(|(previous, next)| previous.checked_add(next))
+        .and_then(|weighted| weighted.checked_div(vesting.locked + locked))
+        .unwrap_or_else(|| panic_with_error!(env, &Errors::MathOverflow));
+
+    vesting.locked += locked;
+    vesting.start = sequence;
+    // The weighted `ledgers` never exceeds the longer schedule, only the ledger sequence itself can run out
+    vesting.end = sequence.saturating_add(ledgers as u32);
+
+    set_vesting(env, farmer.clone(), &vesting);
+    set_farm_vested(env, get_farm_vested(env) + locked);
+
+    locked
+}
+
+// Moves whatever has vested since `start` from `locked` to `unlocked` and restarts the schedule from the current ledger
+fn release_vesting(env: &Env, vesting: &mut Vesting) {
+    let sequence = env.ledger().sequence();
+
+    if sequence >= vesting.end {
+        vesting.unlocked += vesting.locked;
+        vesting.locked = 0;
+    } else if sequence > vesting.start {
+        let released = vesting.locked * (sequence - vesting.start) as i128
+            / (vesting.end - vesting.start) as i128;
+
+        vesting.unlocked += released;
+        vesting.locked -= released;
+    }
+
+    vesting.start = sequence.min(vesting.end);
+}
+
+// A closed block's summary as it was finalized, from the ring buffer or a greenhouse
+// Only the open block, or a closed one whose summary is gone, is summarized from its temporary entry
+fn find_summary(env: &Env, index: u32) -> BlockSummary {
+    if index < get_farm_index(env) {
+        if let Some(summary) = get_block_summary(env, index)
+            .or_else(|| get_greenhouse(env, index).map(|greenhouse| greenhouse.summary))
+        {
+            return summary;
+        }
+    }
+
+    get_block(env, index)
+        .map(|block| summarize_block(env, index, &block))
+        .unwrap_or_else(|| panic_with_error!(env, &Errors::BlockMissing))
+}
+
+fn summarize_block(env: &Env, index: u32, block: &Block) -> BlockSummary {
+    // Only the open block is still projected at the current fee, one closed before fees existed paid none
+    let fee = block.fee.unwrap_or_else(|| {
+        if index < get_farm_index(env) {
+            0
+        } else {
+            generate_fee(env)
+        }
+    });
+
+    BlockSummary {
+        index,
+        min_gap: block.min_gap,
+        min_stake: block.min_stake,
+        min_zeros: block.min_zeros,
+        max_gap: block.max_gap,
+        max_stake: block.max_stake,
+        max_zeros: block.max_zeros,
+        normalized_total: block.normalized_total,
+        reward_pool: get_farm_reward(env) - fee + block.staked_total,
+        normalization: block.normalization.clone(),
+    }
+}
+
+// The farmer's pail and whether it was greenhoused
+fn find_pail(env: &Env, farmer: &Address, index: u32) -> (Pail, bool) {
+    match get_pail(env, farmer.clone(), index) {
+        Some(pail) => (pail, false),
+        None => (
+            get_greenhouse_pail(env, farmer.clone(), index)
+                .unwrap_or_else(|| panic_with_error!(env, &Errors::PailMissing)),
+            true,
+        ),
     }
 }
 
@@ -224,10 +963,26 @@ fn new_farm_block(env: &Env) -> Block {
         entropy: BytesN::from_array(env, &[0; 32]),
         staked_total: 0,
         normalized_total: 0,
+        // the farm block is only an accumulator, its algorithm is never read and its difficulty is the scale of its zeros
+        algorithm: HashAlgorithm::Keccak256,
+        difficulty: Difficulty::Bits,
+        normalization: Normalization::default(),
+        pails: 0,
+        fee: None,
     }
 }
 
-fn new_block(env: &Env, farm_block: &Block) -> Block {
+fn new_block(env: &Env, index: u32, farm_block: &Block) -> Block {
+    let difficulty = Difficulty::Bits;
+    // zeros counted on another scale would skew the new block's bounds, it starts from none instead
+    let zeros = |zeros: u32, unset: u32| {
+        if zeros == unset || farm_block.difficulty != difficulty {
+            0
+        } else {
+            zeros
+        }
+    };
+
     Block {
         timestamp: env.ledger().timestamp(),
         min_gap: if farm_block.min_gap == u32::MAX {
@@ -240,11 +995,7 @@ fn new_block(env: &Env, farm_block: &Block) -> Block {
         } else {
             farm_block.min_stake
         },
-        min_zeros: if farm_block.min_zeros == u32::MAX {
-            0
-        } else {
-            farm_block.min_zeros
-        },
+        min_zeros: zeros(farm_block.min_zeros, u32::MAX),
         max_gap: if farm_block.max_gap == u32::MIN {
             0
         } else {
@@ -255,85 +1006,105 @@ fn new_block(env: &Env, farm_block: &Block) -> Block {
         } else {
             farm_block.max_stake
         },
-        max_zeros: if farm_block.max_zeros == u32::MIN {
-            0
-        } else {
-            farm_block.max_zeros
-        },
+        max_zeros: zeros(farm_block.max_zeros, u32::MIN),
         entropy: farm_block.entropy.clone(),
         staked_total: 0,
         normalized_total: 0,
+        algorithm: get_farm_algorithm(env, index),
+        difficulty,
+        normalization: get_farm_normalization(env),
+        pails: 0,
+        fee: None,
+    }
+}
+
+// The last 32 bytes of the farmer's XDR, copied straight out of the serialized address
+pub(crate) fn generate_farmer_key(env: &Env, farmer: &Address) -> [u8; 32] {
+    generate_xdr_key(&farmer.to_xdr(env))
+}
+
+// Slicing first costs an extra host object per `work`, so only encodings too long for the buffer take that path
+pub(crate) fn generate_xdr_key(xdr: &Bytes) -> [u8; 32] {
+    // G- addresses are 44 bytes of XDR, C- addresses 40 and muxed M- addresses 48
+    let mut xdr_bytes = [0u8; 48];
+    let mut key = [0u8; 32];
+    let length = xdr.len() as usize;
+
+    if length > xdr_bytes.len() {
+        xdr.slice(xdr.len() - 32..).copy_into_slice(&mut key);
+    } else {
+        xdr.copy_into_slice(&mut xdr_bytes[..length]);
+        key.copy_from_slice(&xdr_bytes[length - 32..length]);
     }
+
+    key
 }
 
 fn generate_hash(
     env: &Env,
+    algorithm: &HashAlgorithm,
     index: &u32,
     nonce: &u64,
     entropy: &BytesN<32>,
     farmer: &Address,
 ) -> BytesN<32> {
-    let mut hash_array = [0u8; 76];
+    let hash_array = kale_core::generate_preimage(
+        *index,
+        *nonce,
+        &entropy.to_array(),
+        &generate_farmer_key(env, farmer),
+    );
+    let hash_bytes = Bytes::from_array(env, &hash_array);
 
-    let mut farmer_array = [0u8; 32];
-    let farmer_bytes = farmer.to_xdr(env);
-    farmer_bytes
-        .slice(farmer_bytes.len() - 32..)
-        .copy_into_slice(&mut farmer_array);
+    match algorithm {
+        HashAlgorithm::Keccak256 => env.crypto().keccak256(&hash_bytes).to_bytes(),
+        HashAlgorithm::Sha256 => env.crypto().sha256(&hash_bytes).to_bytes(),
+    }
+}
 
-    hash_array[..4].copy_from_slice(&index.to_be_bytes());
-    hash_array[4..12].copy_from_slice(&nonce.to_be_bytes());
-    hash_array[12..44].copy_from_slice(&entropy.to_array());
-    hash_array[44..].copy_from_slice(&farmer_array);
+fn generate_reward(env: &Env, summary: &BlockSummary, gap: u32, stake: i128, zeros: u32) -> Reward {
+    let normalizations = generate_normalizations(
+        env,
+        &summary.into(),
+        &summary.normalization,
+        gap,
+        stake,
+        zeros,
+    );
+    let (normalized_gap, normalized_stake, normalized_zeros) = normalizations;
 
-    env.crypto()
-        .keccak256(&Bytes::from_array(env, &hash_array))
-        .to_bytes()
+    let reward = kale_core::generate_reward(
+        normalizations,
+        summary.reward_pool,
+        summary.normalized_total,
+        stake,
+    )
+    .unwrap_or_else(|error| panic_with_core_error(env, error));
+
+    Reward {
+        reward,
+        normalized_gap,
+        normalized_stake,
+        normalized_zeros,
+    }
 }
 
 fn generate_normalizations(
     env: &Env,
-    block: &Block,
+    bounds: &kale_core::Bounds,
+    normalization: &Normalization,
     gap: u32,
     stake: i128,
     zeros: u32,
 ) -> (i128, i128, i128) {
-    // TODO should be impossible to hit (consider dropping)
-    if block.max_gap < block.min_gap
-        || block.max_stake < block.min_stake
-        || block.max_zeros < block.min_zeros
-    {
-        panic_with_error!(&env, &Errors::BlockInvalid);
-    }
-
-    // Calculate ranges
-    let range_gap = (block.max_gap - block.min_gap).max(1) as i128;
-    let range_stake = (block.max_stake - block.min_stake).max(1);
-    let range_zeros = (block.max_zeros - block.min_zeros).max(1) as i128;
-
-    // Find largest range for scaling
-    let max_range = range_gap.max(range_stake).max(range_zeros);
-
-    // Set minimum threshold (1% of max_range)
-    // This is intended to prevent normalization from being too small
-    // Won't disable zero reward claim but will decrease their likelihood
-    let min_threshold = (max_range / 100).max(1);
-
-    // Clamp inputs to valid ranges
-    let gap = gap.max(block.min_gap).min(block.max_gap);
-    let stake = stake.max(block.min_stake).min(block.max_stake);
-    let zeros = zeros.max(block.min_zeros).min(block.max_zeros);
-
-    // Scale each value relative to max_range
-    let normalized_gap = ((gap - block.min_gap) as i128)
-        .fixed_mul_floor(&env, &max_range, &range_gap)
-        .max(min_threshold);
-    let normalized_stake = ((stake - block.min_stake) as i128)
-        .fixed_mul_floor(&env, &max_range, &range_stake)
-        .max(min_threshold);
-    let normalized_zeros = ((zeros - block.min_zeros) as i128)
-        .fixed_mul_floor(&env, &max_range, &range_zeros)
-        .max(min_threshold);
-
-    (normalized_gap, normalized_stake, normalized_zeros)
+    kale_core::generate_normalizations(bounds, &normalization.into(), gap, stake, zeros)
+        .unwrap_or_else(|error| panic_with_core_error(env, error))
+}
+
+fn panic_with_core_error(env: &Env, error: kale_core::Error) -> ! {
+    match error {
+        // TODO should be impossible to hit (consider dropping)
+        kale_core::Error::BoundsInvalid => panic_with_error!(env, &Errors::BlockInvalid),
+        kale_core::Error::Overflow => panic_with_error!(env, &Errors::MathOverflow),
+    }
 }
@@ -1,40 +1,68 @@
 use crate::ContractArgs;
 use soroban_sdk::{
-    auth::{Context, CustomAccountInterface},
+    auth::{Context, ContractContext, CustomAccountInterface},
     contractimpl,
     crypto::Hash,
-    panic_with_error, vec, Address, BytesN, Env, Val, Vec,
+    panic_with_error, symbol_short, token, vec, Address, Bytes, BytesN, Env, Symbol, TryFromVal,
+    Vec,
 };
 
 use crate::{
+    contract_farm::{
+        check_locked, close_final_block, forfeit_greenhoused, forfeit_pail, forfeit_vesting,
+    },
+    contract_season::forfeit_bonus,
     errors::Errors,
     storage::{
-        extend_instance_ttl, get_farm_homesteader, get_farm_paused, has_farm_homesteader,
-        set_farm_asset, set_farm_homesteader, set_farm_paused,
+        extend_instance_ttl, get_block, get_farm_algorithm, get_farm_asset, get_farm_greenhoused,
+        get_farm_homesteader, get_farm_index, get_farm_mode, get_farm_paused, get_farm_policy,
+        get_farm_seasons, get_farm_signers, get_farm_summaries, get_farm_sunset,
+        get_farm_unclaimed, get_farm_vested, get_season, has_farm_homesteader, is_blocked,
+        remove_block, remove_blocked, remove_farm_refunds, set_blocked,
+        set_farm_algorithm_schedule, set_farm_asset, set_farm_fee, set_farm_homesteader,
+        set_farm_interval, set_farm_mode, set_farm_normalization, set_farm_paused, set_farm_policy,
+        set_farm_refunds, set_farm_reward, set_farm_seasons, set_farm_signers, set_farm_sunset,
+        set_farm_vesting, set_season,
+    },
+    types::{
+        AlgorithmSchedule, AssetMode, AuthPolicy, Block, FeeSchedule, HashAlgorithm, Multisig,
+        Normalization, Season, Signature, Sunset, VestingSchedule,
     },
-    types::Storage,
-    Contract, ContractClient, HomesteadTrait,
+    Contract, ContractClient, HomesteadTrait, SEASON_LEADERS, SUNSET_WINDOW,
 };
 
 #[contractimpl]
 impl HomesteadTrait for Contract {
-    fn __constructor(env: Env, farmer: Address, asset: Address) {
+    fn __constructor(
+        env: Env,
+        farmer: Address,
+        asset: Address,
+        interval: u64,
+        reward: i128,
+        mode: AssetMode,
+    ) {
         farmer.require_auth();
 
         if has_farm_homesteader(&env) {
             panic_with_error!(&env, &Errors::HomesteadExists);
         }
 
+        // `interval` is in seconds and `reward` is minted per block on top of any forfeited stake
+        if interval == 0 || reward < 0 {
+            panic_with_error!(&env, &Errors::ConfigInvalid);
+        }
+
         set_farm_homesteader(&env, &farmer);
         set_farm_asset(&env, &asset);
+        set_farm_interval(&env, interval);
+        set_farm_reward(&env, reward);
+        set_farm_mode(&env, mode);
 
         extend_instance_ttl(&env);
     }
 
     fn upgrade(env: Env, hash: BytesN<32>) {
-        let homesteader = get_farm_homesteader(&env);
-
-        homesteader.require_auth();
+        require_homestead_auth(&env);
 
         env.deployer().update_current_contract_wasm(hash);
 
@@ -42,10 +70,9 @@ impl HomesteadTrait for Contract {
     }
 
     fn pause(env: Env) {
-        let homesteader = get_farm_homesteader(&env);
         let paused = get_farm_paused(&env);
 
-        homesteader.require_auth();
+        require_homestead_auth(&env);
 
         if paused {
             panic_with_error!(&env, &Errors::FarmPaused);
@@ -57,12 +84,11 @@ impl HomesteadTrait for Contract {
     }
 
     fn unpause(env: Env) {
-        let homesteader = get_farm_homesteader(&env);
         let paused = get_farm_paused(&env);
 
-        homesteader.require_auth();
+        require_homestead_auth(&env);
 
-        if paused {
+        if !paused {
             panic_with_error!(&env, &Errors::FarmNotPaused);
         }
 
@@ -72,30 +98,506 @@ impl HomesteadTrait for Contract {
     }
 
     fn remove_block(env: Env, index: u32) {
-        let homesteader = get_farm_homesteader(&env);
+        require_homestead_auth(&env);
+
+        let block = get_block(&env, index)
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::BlockMissing));
+
+        if !is_settled(&env, index, get_farm_index(&env), &block) {
+            panic_with_error!(&env, &Errors::BlockNotSettled);
+        }
+
+        remove_block(&env, index);
+    }
+
+    fn prune_blocks(env: Env, start: u32, end: u32) -> u32 {
+        require_homestead_auth(&env);
+
+        let farm_index = get_farm_index(&env);
+        let mut removed = 0;
+
+        // Missing and unsettled blocks are skipped rather than failing the whole batch
+        for index in start..end.min(farm_index) {
+            if let Some(block) = get_block(&env, index) {
+                if is_settled(&env, index, farm_index, &block) {
+                    remove_block(&env, index);
+                    removed += 1;
+                }
+            }
+        }
+
+        removed
+    }
+
+    fn schedule_algorithm(env: Env, index: u32, algorithm: HashAlgorithm) {
+        let farm_index = get_farm_index(&env);
+
+        require_homestead_auth(&env);
+
+        // The current block's algorithm is locked in, only future blocks may switch
+        if index <= farm_index {
+            panic_with_error!(&env, &Errors::ScheduleInvalid);
+        }
+
+        // Whatever is in effect for the current block becomes the fallback for blocks before `index`
+        let schedule = AlgorithmSchedule {
+            index,
+            algorithm,
+            previous: get_farm_algorithm(&env, farm_index),
+        };
+
+        set_farm_algorithm_schedule(&env, &schedule);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn set_normalization(env: Env, normalization: Normalization) {
+        require_homestead_auth(&env);
+
+        if normalization.gap_weight == 0
+            && normalization.stake_weight == 0
+            && normalization.zeros_weight == 0
+        {
+            panic_with_error!(&env, &Errors::NormalizationInvalid);
+        }
+
+        // Blocks snapshot the normalization when they open so `work` and `harvest` always agree
+        set_farm_normalization(&env, &normalization);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn set_vesting(env: Env, schedule: VestingSchedule) {
+        require_homestead_auth(&env);
+
+        if schedule.bps > 10_000 || (schedule.bps > 0 && schedule.ledgers == 0) {
+            panic_with_error!(&env, &Errors::VestingInvalid);
+        }
+
+        // Applies from the next harvest on, anything still locked is weighted in with its own end
+        set_farm_vesting(&env, &schedule);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn set_fee(env: Env, fee: FeeSchedule) {
+        require_homestead_auth(&env);
+
+        if fee.bps > 10_000 {
+            panic_with_error!(&env, &Errors::FeeInvalid);
+        }
+
+        // Applies to every block closing from here on, including the one currently open
+        set_farm_fee(&env, &fee);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn set_auth_policy(env: Env, policy: AuthPolicy) {
+        require_homestead_auth(&env);
+
+        if policy.max_amount < 0 {
+            panic_with_error!(&env, &Errors::PolicyInvalid);
+        }
+
+        set_farm_policy(&env, &policy);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn enter_emergency(env: Env, indexes: Vec<u32>) {
+        require_homestead_auth(&env);
+
+        let index = get_farm_index(&env);
+
+        // Only the open block's stakes are still held apart, a closed block's pails get harvested instead
+        if indexes.iter().any(|refund| refund != index) {
+            panic_with_error!(&env, &Errors::RefundNotAllowed);
+        }
 
-        homesteader.require_auth();
+        // Nothing new gets planted while stakes are being handed back
+        set_farm_paused(&env, true);
+        set_farm_refunds(&env, &indexes);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn exit_emergency(env: Env) {
+        require_homestead_auth(&env);
+
+        // The farm stays paused until the homesteader unpauses it
+        remove_farm_refunds(&env);
 
-        env.storage().temporary().remove(&Storage::Block(index));
+        extend_instance_ttl(&env);
+    }
+
+    fn begin_sunset(env: Env, final_index: u32) {
+        require_homestead_auth(&env);
+
+        // The final block can be moved until it has closed, but never behind the current one
+        if final_index < get_farm_index(&env)
+            || get_farm_sunset(&env).is_some_and(|sunset| sunset.closed.is_some())
+        {
+            panic_with_error!(&env, &Errors::SunsetInvalid);
+        }
+
+        set_farm_sunset(
+            &env,
+            &Sunset {
+                final_index,
+                closed: None,
+                complete: false,
+            },
+        );
+
+        extend_instance_ttl(&env);
+    }
+
+    fn complete_sunset(env: Env, new_admin: Address) {
+        require_homestead_auth(&env);
+
+        let mut sunset = get_farm_sunset(&env)
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::SunsetNotReady));
+
+        // Farmers get `SUNSET_WINDOW` ledgers from the final block closing to harvest it
+        let ready = close_final_block(&env, &mut sunset)
+            && sunset
+                .closed
+                .is_some_and(|closed| env.ledger().sequence() >= closed + SUNSET_WINDOW);
+
+        if !ready {
+            panic_with_error!(&env, &Errors::SunsetNotReady);
+        }
+
+        // Once the farm lets go of the asset nothing more can be paid, so vestings, greenhoused pails and season bonuses go first
+        // A blocked farmer can't settle theirs, `forfeit_claims` clears them instead
+        if get_farm_vested(&env) > 0
+            || get_farm_greenhoused(&env) > 0
+            || get_farm_unclaimed(&env) > 0
+        {
+            panic_with_error!(&env, &Errors::ClaimsOutstanding);
+        }
+
+        let asset = get_farm_asset(&env);
+
+        match get_farm_mode(&env) {
+            AssetMode::Mint => token::StellarAssetClient::new(&env, &asset).set_admin(&new_admin),
+            // An escrow farm was never the asset's admin, whatever it still holds goes to `new_admin` instead
+            AssetMode::Escrow => {
+                let asset_client = token::Client::new(&env, &asset);
+                let balance = asset_client.balance(&env.current_contract_address());
+
+                if balance > 0 {
+                    asset_client.transfer(&env.current_contract_address(), &new_admin, &balance);
+                }
+            }
+        }
+
+        sunset.complete = true;
+
+        set_farm_sunset(&env, &sunset);
+
+        env.events()
+            .publish((symbol_short!("sunset"), new_admin), sunset.final_index);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn create_season(env: Env, start: u32, end: u32, bonus: i128, leaders: u32) -> u32 {
+        require_homestead_auth(&env);
+
+        let season = get_farm_seasons(&env);
+
+        // Seasons can't overlap the one before, one ending at the last index leaves no room for another
+        let after = match season
+            .checked_sub(1)
+            .and_then(|previous| get_season(&env, previous))
+        {
+            Some(previous) => previous
+                .end
+                .checked_add(1)
+                .unwrap_or_else(|| panic_with_error!(&env, &Errors::SeasonInvalid)),
+            None => 0,
+        };
+
+        // Nor reach back into blocks already open, only a farm that hasn't opened its first block can start at 0
+        let index = get_farm_index(&env);
+        let next = if index == 0 && get_block(&env, 0).is_none() {
+            0
+        } else {
+            index + 1
+        };
+
+        if start < next
+            || start < after
+            || end < start
+            || bonus < 0
+            || leaders == 0
+            || leaders > SEASON_LEADERS
+        {
+            panic_with_error!(&env, &Errors::SeasonInvalid);
+        }
+
+        set_season(
+            &env,
+            season,
+            &Season {
+                start,
+                end,
+                bonus,
+                leaders,
+                leaderboard: Vec::new(&env),
+            },
+        );
+        set_farm_seasons(&env, season + 1);
+
+        extend_instance_ttl(&env);
+
+        season
+    }
+
+    fn set_signers(env: Env, multisig: Multisig) {
+        require_homestead_auth(&env);
+
+        check_multisig(&env, &multisig);
+
+        // From here on the homesteader's key alone no longer runs the farm
+        set_farm_signers(&env, &multisig);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn set_threshold(env: Env, threshold: u32) {
+        require_homestead_auth(&env);
+
+        let mut multisig = get_farm_signers(&env)
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::SignersInvalid));
+
+        multisig.threshold = threshold;
+
+        check_multisig(&env, &multisig);
+
+        set_farm_signers(&env, &multisig);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn block_farmer(env: Env, farmer: Address, forfeit: bool) {
+        require_homestead_auth(&env);
+
+        set_blocked(&env, farmer.clone());
+
+        // Otherwise the open pail just sits there, unharvestable until the farmer is unblocked
+        if forfeit {
+            if let Some((index, stake)) = forfeit_pail(&env, &farmer) {
+                env.events()
+                    .publish((symbol_short!("forfeit"), farmer.clone()), (index, stake));
+            }
+        }
+
+        env.events()
+            .publish((symbol_short!("blocked"), farmer), forfeit);
+
+        extend_instance_ttl(&env);
+    }
+
+    fn forfeit_claims(env: Env, farmer: Address, greenhoused: Vec<u32>, seasons: Vec<u32>) {
+        require_homestead_auth(&env);
+
+        // Only a blocked farmer is unable to settle their own claims
+        if !is_blocked(&env, farmer.clone()) {
+            panic_with_error!(&env, &Errors::FarmerNotBlocked);
+        }
+
+        let vested = forfeit_vesting(&env, &farmer);
+
+        for index in greenhoused.iter() {
+            forfeit_greenhoused(&env, &farmer, index);
+        }
+
+        for season in seasons.iter() {
+            forfeit_bonus(&env, &farmer, season);
+        }
+
+        env.events().publish(
+            (symbol_short!("forfeited"), farmer),
+            (vested, greenhoused, seasons),
+        );
+
+        extend_instance_ttl(&env);
+    }
+
+    fn unblock_farmer(env: Env, farmer: Address) {
+        require_homestead_auth(&env);
+
+        remove_blocked(&env, farmer.clone());
+
+        env.events()
+            .publish((symbol_short!("unblocked"), farmer), ());
+
+        extend_instance_ttl(&env);
     }
 }
 
 #[contractimpl]
 impl CustomAccountInterface for Contract {
     type Error = Errors;
-    type Signature = Option<Vec<Val>>;
+    type Signature = Option<Vec<Signature>>;
 
     #[allow(non_snake_case)]
     fn __check_auth(
         env: Env,
-        _signature_payload: Hash<32>,
-        _signatures: Option<Vec<Val>>,
-        _auth_contexts: Vec<Context>,
+        signature_payload: Hash<32>,
+        signatures: Option<Vec<Signature>>,
+        auth_contexts: Vec<Context>,
     ) -> Result<(), Errors> {
-        let homesteader = get_farm_homesteader(&env);
+        // A completed sunset has handed everything over, the farm signs for nothing anymore
+        if get_farm_sunset(&env).is_some_and(|sunset| sunset.complete) {
+            return Err(Errors::FarmLocked);
+        }
+
+        let asset = get_farm_asset(&env);
+        let policy = get_farm_policy(&env);
+
+        // The homestead's approval only covers what the policy allows, checked before asking for it
+        for context in auth_contexts.iter() {
+            check_context(&env, &policy, &asset, context)?;
+        }
 
-        homesteader.require_auth_for_args(vec![&env]);
+        match get_farm_signers(&env) {
+            Some(multisig) => check_signatures(&env, &multisig, signature_payload, signatures)?,
+            None => get_farm_homesteader(&env).require_auth_for_args(vec![&env]),
+        }
 
         Ok(())
     }
 }
+
+// With a signer set the farm signs for its own admin calls, which routes them through `__check_auth`
+fn require_homestead_auth(env: &Env) {
+    check_locked(env);
+
+    match get_farm_signers(env) {
+        Some(_) => env.current_contract_address().require_auth(),
+        None => get_farm_homesteader(env).require_auth(),
+    }
+}
+
+// Only closed blocks can go, once their pails are gone or the block is old enough that any left are abandoned
+// The open block never can, the next `plant` would open a fresh one and strand its pails
+fn is_settled(env: &Env, index: u32, farm_index: u32, block: &Block) -> bool {
+    index < farm_index && (block.pails == 0 || farm_index - index >= get_farm_summaries(env))
+}
+
+fn check_multisig(env: &Env, multisig: &Multisig) {
+    if multisig.threshold == 0 || multisig.threshold > multisig.signers.len() {
+        panic_with_error!(env, &Errors::SignersInvalid);
+    }
+
+    // Strictly ascending keys keep the set free of duplicates
+    for (previous, signer) in multisig.signers.iter().zip(multisig.signers.iter().skip(1)) {
+        if previous >= signer {
+            panic_with_error!(env, &Errors::SignersInvalid);
+        }
+    }
+}
+
+fn check_signatures(
+    env: &Env,
+    multisig: &Multisig,
+    signature_payload: Hash<32>,
+    signatures: Option<Vec<Signature>>,
+) -> Result<(), Errors> {
+    let signatures = signatures.ok_or(Errors::SignaturesInvalid)?;
+
+    if signatures.len() < multisig.threshold {
+        return Err(Errors::SignaturesInvalid);
+    }
+
+    let payload: Bytes = signature_payload.to_bytes().into();
+    let mut previous: Option<BytesN<32>> = None;
+
+    for signature in signatures.iter() {
+        // Ascending keys stop one signer from counting twice towards the threshold
+        if previous.is_some_and(|previous| previous >= signature.public_key)
+            || !multisig.signers.contains(&signature.public_key)
+        {
+            return Err(Errors::SignaturesInvalid);
+        }
+
+        // Traps on a bad signature
+        env.crypto()
+            .ed25519_verify(&signature.public_key, &payload, &signature.signature);
+
+        previous = Some(signature.public_key);
+    }
+
+    Ok(())
+}
+
+fn check_context(
+    env: &Env,
+    policy: &AuthPolicy,
+    asset: &Address,
+    context: Context,
+) -> Result<(), Errors> {
+    // The farm never signs for deploying contracts
+    let Context::Contract(ContractContext {
+        contract,
+        fn_name,
+        args,
+    }) = context
+    else {
+        return Err(Errors::ContextDenied);
+    };
+
+    if contract == env.current_contract_address() {
+        return Ok(());
+    }
+
+    if contract != *asset || !policy.functions.contains(&fn_name) {
+        return Err(Errors::ContextDenied);
+    }
+
+    if fn_name == symbol_short!("set_admin") {
+        let new_admin = args
+            .get(0)
+            .and_then(|new_admin| Address::try_from_val(env, &new_admin).ok());
+
+        if new_admin.is_none() || new_admin != policy.successor {
+            return Err(Errors::ContextDenied);
+        }
+    }
+
+    if let Some(index) = amount_index(env, &fn_name) {
+        let amount = args
+            .get(index)
+            .and_then(|amount| i128::try_from_val(env, &amount).ok())
+            .ok_or(Errors::ContextDenied)?;
+
+        if amount > policy.max_amount {
+            return Err(Errors::AmountTooHigh);
+        }
+    }
+
+    Ok(())
+}
+
+// Where SEP-41 and the SAC admin interface put the amount, after the addresses it moves between
+fn amount_index(env: &Env, fn_name: &Symbol) -> Option<u32> {
+    if *fn_name == symbol_short!("mint")
+        || *fn_name == symbol_short!("burn")
+        || *fn_name == symbol_short!("clawback")
+    {
+        Some(1)
+    } else if *fn_name == symbol_short!("transfer")
+        || *fn_name == symbol_short!("approve")
+        || *fn_name == symbol_short!("burn_from")
+    {
+        Some(2)
+    } else if *fn_name == Symbol::new(env, "transfer_from") {
+        Some(3)
+    } else {
+        None
+    }
+}
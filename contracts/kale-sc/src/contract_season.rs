@@ -0,0 +1,206 @@
+use crate::ContractArgs;
+use soroban_sdk::{contractimpl, panic_with_error, Address, Env};
+
+use crate::{
+    contract_farm::{check_farmer, pay_bonus},
+    errors::Errors,
+    storage::{
+        extend_instance_ttl, get_farm_index, get_farm_seasons, get_farm_summaries, get_farm_sunset,
+        get_farm_unclaimed, get_season, get_season_contribution, set_farm_unclaimed, set_season,
+        set_season_contribution,
+    },
+    types::{Season, Standing},
+    Contract, ContractClient, SeasonTrait, SUNSET_WINDOW,
+};
+
+#[contractimpl]
+impl SeasonTrait for Contract {
+    fn claim_season_bonus(env: Env, farmer: Address, season: u32) -> i128 {
+        // No auth_require here, like `harvest` the claim can only ever pay the `farmer`
+
+        check_farmer(&env, &farmer);
+
+        let (mut entry, position, mut standing) = take_standing(&env, &farmer, season);
+
+        let total: i128 = entry
+            .leaderboard
+            .iter()
+            .map(|standing| standing.contribution)
+            .sum();
+        let bonus = entry
+            .bonus
+            .checked_mul(standing.contribution)
+            .unwrap_or_else(|| panic_with_error!(&env, &Errors::MathOverflow))
+            / total;
+
+        standing.claimed = true;
+        entry.leaderboard.set(position, standing);
+
+        set_season(&env, season, &entry);
+        set_farm_unclaimed(&env, get_farm_unclaimed(&env).saturating_sub(1));
+        pay_bonus(&env, &farmer, bonus);
+
+        extend_instance_ttl(&env);
+
+        bonus
+    }
+
+    fn season(env: Env, season: u32) -> Option<Season> {
+        get_season(&env, season)
+    }
+
+    fn season_count(env: Env) -> u32 {
+        get_farm_seasons(&env)
+    }
+
+    fn season_contribution(env: Env, farmer: Address, season: u32) -> i128 {
+        get_season_contribution(&env, season, farmer)
+    }
+}
+
+// Adds a harvest's earnings to the farmer's total for the season the harvested block falls in, and re-ranks them
+// Earnings are a share of each block's pool so they compare across blocks, unlike normalized contributions
+pub(crate) fn record_contribution(env: &Env, farmer: &Address, index: u32, contribution: i128) {
+    if contribution <= 0 {
+        return;
+    }
+
+    let Some((season, mut entry)) = find_season(env, index) else {
+        return;
+    };
+
+    // A final leaderboard may already be paying out
+    if is_season_over(env, &entry) {
+        return;
+    }
+
+    let total = get_season_contribution(env, season, farmer.clone()) + contribution;
+
+    set_season_contribution(env, season, farmer.clone(), total);
+
+    let ranked = entry
+        .leaderboard
+        .iter()
+        .any(|standing| standing.farmer == *farmer);
+
+    // Off a full leaderboard and not past its last standing, so the season entry stays untouched
+    if !ranked
+        && entry.leaderboard.len() >= entry.leaders
+        && entry
+            .leaderboard
+            .last()
+            .is_some_and(|last| last.contribution >= total)
+    {
+        return;
+    }
+
+    // Nothing is claimed before the season is over, so every standing the leaderboard gains or drops is unclaimed
+    let standings = entry.leaderboard.len();
+
+    rank_farmer(&mut entry, farmer, total);
+    set_season(env, season, &entry);
+
+    if entry.leaderboard.len() > standings {
+        set_farm_unclaimed(env, get_farm_unclaimed(env) + 1);
+    }
+}
+
+// Gives up the farmer's unclaimed bonus for a finished season, the rest of the leaderboard's shares are unchanged
+pub(crate) fn forfeit_bonus(env: &Env, farmer: &Address, season: u32) {
+    let (mut entry, position, mut standing) = take_standing(env, farmer, season);
+
+    standing.claimed = true;
+    entry.leaderboard.set(position, standing);
+
+    set_season(env, season, &entry);
+    set_farm_unclaimed(env, get_farm_unclaimed(env).saturating_sub(1));
+}
+
+// The season and the farmer's unclaimed standing on its final leaderboard, along with its position
+fn take_standing(env: &Env, farmer: &Address, season: u32) -> (Season, u32, Standing) {
+    let entry =
+        get_season(env, season).unwrap_or_else(|| panic_with_error!(env, &Errors::SeasonMissing));
+
+    // The leaderboard is only final once the season's last block can't be harvested from its summary
+    if !is_season_over(env, &entry) {
+        panic_with_error!(env, &Errors::SeasonNotOver);
+    }
+
+    let position = entry
+        .leaderboard
+        .iter()
+        .position(|standing| standing.farmer == *farmer)
+        .unwrap_or_else(|| panic_with_error!(env, &Errors::BonusMissing)) as u32;
+    let standing = entry.leaderboard.get_unchecked(position);
+
+    if standing.claimed {
+        panic_with_error!(env, &Errors::BonusClaimed);
+    }
+
+    (entry, position, standing)
+}
+
+// The season whose blocks include `index`, seasons are created in order and never overlap so walk back from the latest
+fn find_season(env: &Env, index: u32) -> Option<(u32, Season)> {
+    let mut season = get_farm_seasons(env);
+
+    while let Some(previous) = season.checked_sub(1) {
+        season = previous;
+
+        let entry = get_season(env, season)?;
+
+        if index > entry.end {
+            return None;
+        }
+
+        if index >= entry.start {
+            return Some((season, entry));
+        }
+    }
+
+    None
+}
+
+// Blocks stay harvestable from their summaries for a summary window, after that the leaderboard is final
+// A sunsetting farm opens no more blocks, so its seasons are final once the sunset's harvest window is over
+fn is_season_over(env: &Env, entry: &Season) -> bool {
+    get_farm_index(env) > entry.end.saturating_add(get_farm_summaries(env))
+        || get_farm_sunset(env)
+            .and_then(|sunset| sunset.closed)
+            .is_some_and(|closed| env.ledger().sequence() >= closed.saturating_add(SUNSET_WINDOW))
+}
+
+// Moves the farmer to their place on the leaderboard, ties keep whoever got there first ahead
+fn rank_farmer(entry: &mut Season, farmer: &Address, contribution: i128) {
+    if let Some(position) = entry
+        .leaderboard
+        .iter()
+        .position(|standing| standing.farmer == *farmer)
+    {
+        entry.leaderboard.remove(position as u32);
+    }
+
+    let position = entry
+        .leaderboard
+        .iter()
+        .position(|standing| standing.contribution < contribution)
+        .map(|position| position as u32)
+        .unwrap_or(entry.leaderboard.len());
+
+    if position >= entry.leaders {
+        return;
+    }
+
+    entry.leaderboard.insert(
+        position,
+        Standing {
+            farmer: farmer.clone(),
+            contribution,
+            claimed: false,
+        },
+    );
+
+    if entry.leaderboard.len() > entry.leaders {
+        entry.leaderboard.pop_back();
+    }
+}
@@ -16,4 +16,31 @@ pub enum Errors {
     BlockInvalid = 12,
     HashInvalid = 13,
     HarvestNotReady = 14,
+    ScheduleInvalid = 15,
+    NormalizationInvalid = 16,
+    MathOverflow = 17,
+    RestakeInvalid = 18,
+    VestingInvalid = 19,
+    VestingMissing = 20,
+    ConfigInvalid = 21,
+    FeeInvalid = 22,
+    PolicyInvalid = 23,
+    ContextDenied = 24,
+    AmountTooHigh = 25,
+    SignersInvalid = 26,
+    SignaturesInvalid = 27,
+    FarmerBlocked = 28,
+    BlockNotSettled = 29,
+    RefundNotAllowed = 30,
+    SunsetInvalid = 31,
+    SunsetNotReady = 32,
+    FarmSunset = 33,
+    FarmLocked = 34,
+    SeasonInvalid = 35,
+    SeasonMissing = 36,
+    SeasonNotOver = 37,
+    BonusMissing = 38,
+    BonusClaimed = 39,
+    ClaimsOutstanding = 40,
+    FarmerNotBlocked = 41,
 }
@@ -1,20 +1,31 @@
 #![no_std]
 
-use soroban_sdk::{contract, Address, BytesN, Env};
+use soroban_sdk::{contract, Address, BytesN, Env, Vec};
+use types::{
+    AssetMode, AuthPolicy, BlockSummary, FeeSchedule, HashAlgorithm, Multisig, Normalization,
+    Reward, Season, Sunset, SupplyStats, Vesting, VestingSchedule,
+};
 
 mod contract_farm;
 mod contract_homestead;
-mod errors;
+mod contract_season;
+pub mod errors;
 mod storage;
 mod tests;
-mod types;
+pub mod testutils;
+pub mod types;
 
+// Defaults for the KALE farm itself, other farms pick their own `interval` and `reward` at construction
 // Higher BLOCK_INTERVAL means there's more time to mine a block which means we can have more participation without risking problematic congestion
 // Too low and the network could be congested
 // Too high and there's too much time for fast miners to dominate
 pub const BLOCK_INTERVAL: u64 = 60 * 5; // In seconds
 pub const BLOCK_REWARD: i128 = 1_0000000 * BLOCK_INTERVAL as i128 / 60; // base_per_minute * second_interval / seconds_per_minute
 pub const WEEK_OF_LEDGERS: u32 = 60 * 60 * 24 / 5 * 7; // assumes 5 second ledger close times
+pub const SUNSET_WINDOW: u32 = WEEK_OF_LEDGERS; // how long farmers have to harvest once a sunsetting farm's final block closes
+pub const SEASON_LEADERS: u32 = 25; // the most places a season's leaderboard holds, every harvest of a season block rewrites it
+pub const SUMMARY_WINDOW: u64 = 60 * 60 * 24 * 7; // a week of closed blocks outlive their temporary entries, in seconds
+pub const BLOCK_SUMMARIES: u32 = (SUMMARY_WINDOW / BLOCK_INTERVAL) as u32; // the KALE farm's summary ring, other farms size theirs by their `interval`
 
 // TODO add more comments
 
@@ -22,7 +33,14 @@ pub const WEEK_OF_LEDGERS: u32 = 60 * 60 * 24 / 5 * 7; // assumes 5 second ledge
 pub struct Contract;
 
 pub trait HomesteadTrait {
-    fn __constructor(env: Env, farmer: Address, asset: Address);
+    fn __constructor(
+        env: Env,
+        farmer: Address,
+        asset: Address,
+        interval: u64,
+        reward: i128,
+        mode: AssetMode,
+    );
 
     fn upgrade(env: Env, hash: BytesN<32>);
 
@@ -31,6 +49,38 @@ pub trait HomesteadTrait {
     fn unpause(env: Env);
 
     fn remove_block(env: Env, index: u32);
+
+    fn prune_blocks(env: Env, start: u32, end: u32) -> u32;
+
+    fn enter_emergency(env: Env, indexes: Vec<u32>);
+
+    fn exit_emergency(env: Env);
+
+    fn begin_sunset(env: Env, final_index: u32);
+
+    fn complete_sunset(env: Env, new_admin: Address);
+
+    fn create_season(env: Env, start: u32, end: u32, bonus: i128, leaders: u32) -> u32;
+
+    fn schedule_algorithm(env: Env, index: u32, algorithm: HashAlgorithm);
+
+    fn set_normalization(env: Env, normalization: Normalization);
+
+    fn set_vesting(env: Env, schedule: VestingSchedule);
+
+    fn set_fee(env: Env, fee: FeeSchedule);
+
+    fn set_auth_policy(env: Env, policy: AuthPolicy);
+
+    fn set_signers(env: Env, multisig: Multisig);
+
+    fn set_threshold(env: Env, threshold: u32);
+
+    fn block_farmer(env: Env, farmer: Address, forfeit: bool);
+
+    fn forfeit_claims(env: Env, farmer: Address, greenhoused: Vec<u32>, seasons: Vec<u32>);
+
+    fn unblock_farmer(env: Env, farmer: Address);
 }
 
 pub trait FarmTrait {
@@ -39,4 +89,49 @@ pub trait FarmTrait {
     fn work(env: Env, farmer: Address, hash: BytesN<32>, nonce: u64) -> u32;
 
     fn harvest(env: Env, farmer: Address, index: u32) -> i128;
+
+    fn harvest_and_plant(env: Env, farmer: Address, harvest_index: u32, restake_bps: u32) -> i128;
+
+    fn greenhouse(env: Env, farmer: Address, index: u32);
+
+    fn estimate_reward(env: Env, farmer: Address, index: u32) -> Reward;
+
+    fn preview_reward(
+        env: Env,
+        index: u32,
+        farmer: Option<Address>,
+        stake: i128,
+        gap: u32,
+        zeros: u32,
+    ) -> Reward;
+
+    fn block_summary(env: Env, index: u32) -> Option<BlockSummary>;
+
+    fn claim_vested(env: Env, farmer: Address) -> i128;
+
+    fn vesting(env: Env, farmer: Address) -> Option<Vesting>;
+
+    fn treasury(env: Env) -> i128;
+
+    fn fees(env: Env) -> i128;
+
+    fn supply_stats(env: Env) -> SupplyStats;
+
+    fn blocked(env: Env, farmer: Address) -> bool;
+
+    fn refund(env: Env, farmer: Address, index: u32) -> i128;
+
+    fn refunds(env: Env) -> Vec<u32>;
+
+    fn sunset(env: Env) -> Option<Sunset>;
+}
+
+pub trait SeasonTrait {
+    fn claim_season_bonus(env: Env, farmer: Address, season: u32) -> i128;
+
+    fn season(env: Env, season: u32) -> Option<Season>;
+
+    fn season_count(env: Env) -> u32;
+
+    fn season_contribution(env: Env, farmer: Address, season: u32) -> i128;
 }
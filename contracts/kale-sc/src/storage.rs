@@ -1,9 +1,13 @@
-use soroban_sdk::{panic_with_error, Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env, IntoVal, TryFromVal, Val, Vec};
 
 use crate::{
     errors::Errors,
-    types::{Block, Pail, Storage},
-    WEEK_OF_LEDGERS,
+    types::{
+        AlgorithmSchedule, AssetMode, AuthPolicy, Block, BlockSummary, FeeSchedule, Greenhouse,
+        HashAlgorithm, LegacyBlock, Multisig, Normalization, Pail, Season, Storage, Sunset,
+        SupplyStats, Vesting, VestingSchedule,
+    },
+    BLOCK_INTERVAL, BLOCK_REWARD, SUMMARY_WINDOW, WEEK_OF_LEDGERS,
 };
 
 pub fn extend_instance_ttl(env: &Env) {
@@ -43,31 +47,203 @@ pub fn set_farm_asset(env: &Env, asset: &Address) {
         .set::<Storage, Address>(&Storage::HomesteadAsset, asset);
 }
 
-pub fn get_farm_index(env: &Env) -> u32 {
+// Farms constructed before these were configurable fall back to the original constants
+pub fn get_farm_interval(env: &Env) -> u64 {
     env.storage()
         .instance()
-        .get::<Storage, u32>(&Storage::FarmIndex)
-        .unwrap_or(0)
+        .get::<Storage, u64>(&Storage::HomesteadInterval)
+        .unwrap_or(BLOCK_INTERVAL)
+}
+pub fn set_farm_interval(env: &Env, interval: u64) {
+    env.storage()
+        .instance()
+        .set::<Storage, u64>(&Storage::HomesteadInterval, &interval);
 }
-pub fn bump_farm_index(env: &Env, current_farm_index: &mut u32) {
-    *current_farm_index += 1;
 
+pub fn get_farm_reward(env: &Env) -> i128 {
     env.storage()
         .instance()
-        .set::<Storage, u32>(&Storage::FarmIndex, &current_farm_index);
+        .get::<Storage, i128>(&Storage::HomesteadReward)
+        .unwrap_or(BLOCK_REWARD)
+}
+pub fn set_farm_reward(env: &Env, reward: i128) {
+    env.storage()
+        .instance()
+        .set::<Storage, i128>(&Storage::HomesteadReward, &reward);
 }
 
-pub fn get_farm_block(env: &Env) -> Option<Block> {
+pub fn get_farm_mode(env: &Env) -> AssetMode {
     env.storage()
         .instance()
-        .get::<Storage, Block>(&Storage::FarmBlock)
+        .get::<Storage, AssetMode>(&Storage::HomesteadMode)
+        .unwrap_or(AssetMode::Mint)
 }
-pub fn set_farm_block(env: &Env, block: &Block) {
+pub fn set_farm_mode(env: &Env, mode: AssetMode) {
+    env.storage()
+        .instance()
+        .set::<Storage, AssetMode>(&Storage::HomesteadMode, &mode);
+}
+
+pub fn get_farm_algorithm_schedule(env: &Env) -> Option<AlgorithmSchedule> {
+    env.storage()
+        .instance()
+        .get::<Storage, AlgorithmSchedule>(&Storage::HomesteadAlgorithm)
+}
+pub fn set_farm_algorithm_schedule(env: &Env, schedule: &AlgorithmSchedule) {
+    env.storage()
+        .instance()
+        .set::<Storage, AlgorithmSchedule>(&Storage::HomesteadAlgorithm, schedule);
+}
+pub fn get_farm_algorithm(env: &Env, index: u32) -> HashAlgorithm {
+    match get_farm_algorithm_schedule(env) {
+        Some(schedule) if index >= schedule.index => schedule.algorithm,
+        Some(schedule) => schedule.previous,
+        None => HashAlgorithm::Keccak256,
+    }
+}
+
+pub fn get_farm_normalization(env: &Env) -> Normalization {
+    env.storage()
+        .instance()
+        .get::<Storage, Normalization>(&Storage::HomesteadNormalization)
+        .unwrap_or_default()
+}
+pub fn set_farm_normalization(env: &Env, normalization: &Normalization) {
+    env.storage()
+        .instance()
+        .set::<Storage, Normalization>(&Storage::HomesteadNormalization, normalization);
+}
+
+pub fn get_farm_vesting(env: &Env) -> VestingSchedule {
+    env.storage()
+        .instance()
+        .get::<Storage, VestingSchedule>(&Storage::HomesteadVesting)
+        .unwrap_or_default()
+}
+pub fn set_farm_vesting(env: &Env, schedule: &VestingSchedule) {
+    env.storage()
+        .instance()
+        .set::<Storage, VestingSchedule>(&Storage::HomesteadVesting, schedule);
+}
+
+pub fn get_farm_fee(env: &Env) -> Option<FeeSchedule> {
+    env.storage()
+        .instance()
+        .get::<Storage, FeeSchedule>(&Storage::HomesteadFee)
+}
+pub fn set_farm_fee(env: &Env, fee: &FeeSchedule) {
     env.storage()
         .instance()
-        .set::<Storage, Block>(&Storage::FarmBlock, block);
+        .set::<Storage, FeeSchedule>(&Storage::HomesteadFee, fee);
+}
+
+// Until a policy is set the farm signs for nothing but calls on itself
+pub fn get_farm_policy(env: &Env) -> AuthPolicy {
+    env.storage()
+        .instance()
+        .get::<Storage, AuthPolicy>(&Storage::HomesteadPolicy)
+        .unwrap_or(AuthPolicy {
+            functions: Vec::new(env),
+            successor: None,
+            max_amount: 0,
+        })
+}
+pub fn set_farm_policy(env: &Env, policy: &AuthPolicy) {
+    env.storage()
+        .instance()
+        .set::<Storage, AuthPolicy>(&Storage::HomesteadPolicy, policy);
+}
+
+pub fn get_farm_signers(env: &Env) -> Option<Multisig> {
+    env.storage()
+        .instance()
+        .get::<Storage, Multisig>(&Storage::HomesteadSigners)
+}
+pub fn set_farm_signers(env: &Env, signers: &Multisig) {
+    env.storage()
+        .instance()
+        .set::<Storage, Multisig>(&Storage::HomesteadSigners, signers);
+}
+
+// The block indexes open for `refund`, the farm is in emergency mode while there are any
+pub fn get_farm_refunds(env: &Env) -> Vec<u32> {
+    env.storage()
+        .instance()
+        .get::<Storage, Vec<u32>>(&Storage::HomesteadRefunds)
+        .unwrap_or(Vec::new(env))
+}
+pub fn set_farm_refunds(env: &Env, indexes: &Vec<u32>) {
+    env.storage()
+        .instance()
+        .set::<Storage, Vec<u32>>(&Storage::HomesteadRefunds, indexes);
+}
+pub fn remove_farm_refunds(env: &Env) {
+    env.storage()
+        .instance()
+        .remove::<Storage>(&Storage::HomesteadRefunds);
+}
+
+pub fn get_farm_sunset(env: &Env) -> Option<Sunset> {
+    env.storage()
+        .instance()
+        .get::<Storage, Sunset>(&Storage::HomesteadSunset)
+}
+pub fn set_farm_sunset(env: &Env, sunset: &Sunset) {
+    env.storage()
+        .instance()
+        .set::<Storage, Sunset>(&Storage::HomesteadSunset, sunset);
+}
+
+// How many seasons have been created, numbered in block order
+pub fn get_farm_seasons(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get::<Storage, u32>(&Storage::HomesteadSeasons)
+        .unwrap_or(0)
+}
+pub fn set_farm_seasons(env: &Env, seasons: u32) {
+    env.storage()
+        .instance()
+        .set::<Storage, u32>(&Storage::HomesteadSeasons, &seasons);
+}
+
+// `FarmIndex` and `FarmBlock` change far more often than the homestead config so they live in their own
+// persistent entries, keeping every call from loading and rewriting them with the instance
+// Farms deployed before the split still hold them in instance storage until their next write moves them
+pub fn get_farm_index(env: &Env) -> u32 {
+    get_farm_entry::<u32>(env, &Storage::FarmIndex).unwrap_or(0)
+}
+pub fn bump_farm_index(env: &Env, current_farm_index: &mut u32) {
+    *current_farm_index += 1;
+
+    set_farm_entry::<u32>(env, &Storage::FarmIndex, current_farm_index);
 }
 
+// Blocks moved to `FarmBlockV2` and `BlockV2` when they outgrew the original layout, a farm upgraded with blocks in
+// flight still has them under the old keys and migrates them the first time they're read
+pub fn get_farm_block(env: &Env) -> Option<Block> {
+    env.storage()
+        .persistent()
+        .get::<Storage, Block>(&Storage::FarmBlockV2)
+        .or_else(|| {
+            env.storage()
+                .instance()
+                .get::<Storage, LegacyBlock>(&Storage::FarmBlock)
+                .map(Block::from)
+        })
+}
+pub fn set_farm_block(env: &Env, block: &Block) {
+    set_farm_entry::<Block>(env, &Storage::FarmBlockV2, block);
+
+    if env.storage().instance().has::<Storage>(&Storage::FarmBlock) {
+        env.storage()
+            .instance()
+            .remove::<Storage>(&Storage::FarmBlock);
+    }
+}
+
+// Rarely written so it stays with the instance, a persistent entry only has its TTL extended on write
+// and could be archived out from under `plant` while the farm runs unpaused
 pub fn get_farm_paused(env: &Env) -> bool {
     env.storage()
         .instance()
@@ -80,15 +256,83 @@ pub fn set_farm_paused(env: &Env, paused: bool) {
         .set::<Storage, bool>(&Storage::FarmPaused, &paused);
 }
 
+// The open block's stakes held for `Escrow` farms, the rest of the farm's balance is its treasury
+pub fn get_farm_escrow(env: &Env) -> i128 {
+    get_farm_entry::<i128>(env, &Storage::FarmEscrow).unwrap_or(0)
+}
+pub fn set_farm_escrow(env: &Env, escrow: i128) {
+    set_farm_entry::<i128>(env, &Storage::FarmEscrow, &escrow);
+}
+
+pub fn get_farm_supply(env: &Env) -> SupplyStats {
+    get_farm_entry::<SupplyStats>(env, &Storage::FarmSupply).unwrap_or_default()
+}
+pub fn set_farm_supply(env: &Env, supply: &SupplyStats) {
+    set_farm_entry::<SupplyStats>(env, &Storage::FarmSupply, supply);
+}
+
+// Rewards owed through farmers' vestings, locked or not
+pub fn get_farm_vested(env: &Env) -> i128 {
+    get_farm_entry::<i128>(env, &Storage::FarmVested).unwrap_or(0)
+}
+pub fn set_farm_vested(env: &Env, vested: i128) {
+    set_farm_entry::<i128>(env, &Storage::FarmVested, &vested);
+}
+
+// Pails waiting in greenhouses to be harvested
+pub fn get_farm_greenhoused(env: &Env) -> u32 {
+    get_farm_entry::<u32>(env, &Storage::FarmGreenhoused).unwrap_or(0)
+}
+pub fn set_farm_greenhoused(env: &Env, greenhoused: u32) {
+    set_farm_entry::<u32>(env, &Storage::FarmGreenhoused, &greenhoused);
+}
+
+// Leaderboard standings across all seasons whose bonus hasn't been claimed yet
+pub fn get_farm_unclaimed(env: &Env) -> u32 {
+    get_farm_entry::<u32>(env, &Storage::FarmUnclaimed).unwrap_or(0)
+}
+pub fn set_farm_unclaimed(env: &Env, unclaimed: u32) {
+    set_farm_entry::<u32>(env, &Storage::FarmUnclaimed, &unclaimed);
+}
+
+fn get_farm_entry<V: TryFromVal<Env, Val>>(env: &Env, key: &Storage) -> Option<V> {
+    env.storage()
+        .persistent()
+        .get::<Storage, V>(key)
+        .or_else(|| env.storage().instance().get::<Storage, V>(key))
+}
+fn set_farm_entry<V: IntoVal<Env, Val>>(env: &Env, key: &Storage, value: &V) {
+    set_persistent_entry::<V>(env, key, value);
+
+    if env.storage().instance().has::<Storage>(key) {
+        env.storage().instance().remove::<Storage>(key);
+    }
+}
+
 pub fn get_block(env: &Env, index: u32) -> Option<Block> {
     env.storage()
         .temporary()
-        .get::<Storage, Block>(&Storage::Block(index))
+        .get::<Storage, Block>(&Storage::BlockV2(index))
+        .or_else(|| {
+            env.storage()
+                .temporary()
+                .get::<Storage, LegacyBlock>(&Storage::Block(index))
+                .map(Block::from)
+        })
 }
+// A legacy entry left behind is shadowed from here on and expires on its own
 pub fn set_block(env: &Env, index: u32, block: &Block) {
     env.storage()
         .temporary()
-        .set::<Storage, Block>(&Storage::Block(index), block);
+        .set::<Storage, Block>(&Storage::BlockV2(index), block);
+}
+pub fn remove_block(env: &Env, index: u32) {
+    env.storage()
+        .temporary()
+        .remove::<Storage>(&Storage::BlockV2(index));
+    env.storage()
+        .temporary()
+        .remove::<Storage>(&Storage::Block(index));
 }
 
 pub fn has_pail(env: &Env, farmer: Address, index: u32) -> bool {
@@ -113,3 +357,109 @@ pub fn remove_pail(env: &Env, farmer: Address, index: u32) {
 
     env.storage().temporary().remove::<Storage>(&pail_key);
 }
+
+// How many of the farm's blocks close in `SUMMARY_WINDOW`, the size of its summary ring
+pub fn get_farm_summaries(env: &Env) -> u32 {
+    (SUMMARY_WINDOW / get_farm_interval(env)).clamp(1, u32::MAX as u64) as u32
+}
+
+// A ring buffer, each slot is overwritten once the index `get_farm_summaries` blocks later closes
+pub fn get_block_summary(env: &Env, index: u32) -> Option<BlockSummary> {
+    env.storage()
+        .persistent()
+        .get::<Storage, BlockSummary>(&Storage::BlockSummary(index % get_farm_summaries(env)))
+        .filter(|summary| summary.index == index)
+}
+pub fn set_block_summary(env: &Env, summary: &BlockSummary) {
+    set_persistent_entry::<BlockSummary>(
+        env,
+        &Storage::BlockSummary(summary.index % get_farm_summaries(env)),
+        summary,
+    );
+}
+
+pub fn get_greenhouse(env: &Env, index: u32) -> Option<Greenhouse> {
+    env.storage()
+        .persistent()
+        .get::<Storage, Greenhouse>(&Storage::Greenhouse(index))
+}
+pub fn set_greenhouse(env: &Env, index: u32, greenhouse: &Greenhouse) {
+    set_persistent_entry::<Greenhouse>(env, &Storage::Greenhouse(index), greenhouse);
+}
+pub fn remove_greenhouse(env: &Env, index: u32) {
+    env.storage()
+        .persistent()
+        .remove::<Storage>(&Storage::Greenhouse(index));
+}
+
+pub fn get_greenhouse_pail(env: &Env, farmer: Address, index: u32) -> Option<Pail> {
+    let pail_key = Storage::GreenhousePail(farmer, index);
+
+    env.storage().persistent().get::<Storage, Pail>(&pail_key)
+}
+pub fn set_greenhouse_pail(env: &Env, farmer: Address, index: u32, pail: Pail) {
+    let pail_key = Storage::GreenhousePail(farmer, index);
+
+    set_persistent_entry::<Pail>(env, &pail_key, &pail);
+}
+pub fn remove_greenhouse_pail(env: &Env, farmer: Address, index: u32) {
+    let pail_key = Storage::GreenhousePail(farmer, index);
+
+    env.storage().persistent().remove::<Storage>(&pail_key);
+}
+
+pub fn get_vesting(env: &Env, farmer: Address) -> Option<Vesting> {
+    env.storage()
+        .persistent()
+        .get::<Storage, Vesting>(&Storage::Vesting(farmer))
+}
+pub fn set_vesting(env: &Env, farmer: Address, vesting: &Vesting) {
+    set_persistent_entry::<Vesting>(env, &Storage::Vesting(farmer), vesting);
+}
+pub fn remove_vesting(env: &Env, farmer: Address) {
+    env.storage()
+        .persistent()
+        .remove::<Storage>(&Storage::Vesting(farmer));
+}
+
+pub fn is_blocked(env: &Env, farmer: Address) -> bool {
+    env.storage()
+        .persistent()
+        .has::<Storage>(&Storage::Blocked(farmer))
+}
+pub fn set_blocked(env: &Env, farmer: Address) {
+    set_persistent_entry::<bool>(env, &Storage::Blocked(farmer), &true);
+}
+pub fn remove_blocked(env: &Env, farmer: Address) {
+    env.storage()
+        .persistent()
+        .remove::<Storage>(&Storage::Blocked(farmer));
+}
+
+pub fn get_season(env: &Env, season: u32) -> Option<Season> {
+    env.storage()
+        .persistent()
+        .get::<Storage, Season>(&Storage::Season(season))
+}
+pub fn set_season(env: &Env, season: u32, entry: &Season) {
+    set_persistent_entry::<Season>(env, &Storage::Season(season), entry);
+}
+
+pub fn get_season_contribution(env: &Env, season: u32, farmer: Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get::<Storage, i128>(&Storage::SeasonFarmer(season, farmer))
+        .unwrap_or(0)
+}
+pub fn set_season_contribution(env: &Env, season: u32, farmer: Address, contribution: i128) {
+    set_persistent_entry::<i128>(env, &Storage::SeasonFarmer(season, farmer), &contribution);
+}
+
+fn set_persistent_entry<V: IntoVal<Env, Val>>(env: &Env, key: &Storage, value: &V) {
+    let max_ttl = env.storage().max_ttl();
+
+    env.storage().persistent().set::<Storage, V>(key, value);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, max_ttl - WEEK_OF_LEDGERS, max_ttl);
+}
@@ -0,0 +1,181 @@
+#![cfg(test)]
+
+extern crate std;
+
+use soroban_sdk::{
+    auth::{Context, ContractContext, ContractExecutable, CreateContractHostFnContext},
+    symbol_short,
+    testutils::Address as _,
+    vec, Address, BytesN, Env, IntoVal, InvokeError, Symbol, Val, Vec,
+};
+
+use crate::{errors::Errors, testutils::FarmHarness, types::AuthPolicy};
+
+fn call(env: &Env, contract: &Address, fn_name: &str, args: Vec<Val>) -> Context {
+    Context::Contract(ContractContext {
+        contract: contract.clone(),
+        fn_name: Symbol::new(env, fn_name),
+        args,
+    })
+}
+
+fn check_auth(
+    harness: &FarmHarness,
+    contexts: &[Context],
+) -> Result<(), Result<Errors, InvokeError>> {
+    let env = &harness.env;
+    let contexts = Vec::from_slice(env, contexts);
+
+    env.try_invoke_contract_check_auth::<Errors>(
+        &harness.farm,
+        &BytesN::from_array(env, &[0; 32]),
+        ().into_val(env),
+        &contexts,
+    )
+}
+
+#[test]
+fn test_check_auth_default() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farmer = Address::generate(env);
+
+    // Calls on the farm itself are always signed for
+    assert_eq!(
+        check_auth(&harness, &[call(env, &harness.farm, "pause", vec![env])]),
+        Ok(())
+    );
+
+    // Without a policy nothing on the asset is
+    assert_eq!(
+        check_auth(
+            &harness,
+            &[call(
+                env,
+                &harness.asset,
+                "mint",
+                vec![env, farmer.into_val(env), 1i128.into_val(env)]
+            )]
+        ),
+        Err(Ok(Errors::ContextDenied))
+    );
+}
+
+#[test]
+fn test_check_auth_policy() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let farmer = Address::generate(env);
+    let successor = Address::generate(env);
+    let stranger = Address::generate(env);
+
+    let err = farm_client
+        .try_set_auth_policy(&AuthPolicy {
+            functions: vec![env],
+            successor: None,
+            max_amount: -1,
+        })
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::PolicyInvalid.into());
+
+    farm_client.set_auth_policy(&AuthPolicy {
+        functions: vec![env, symbol_short!("clawback"), symbol_short!("set_admin")],
+        successor: Some(successor.clone()),
+        max_amount: 100,
+    });
+
+    let clawback = |amount: i128| {
+        call(
+            env,
+            &harness.asset,
+            "clawback",
+            vec![env, farmer.into_val(env), amount.into_val(env)],
+        )
+    };
+    let set_admin = |new_admin: &Address| {
+        call(
+            env,
+            &harness.asset,
+            "set_admin",
+            vec![env, new_admin.into_val(env)],
+        )
+    };
+
+    assert_eq!(
+        check_auth(
+            &harness,
+            &[
+                clawback(100),
+                set_admin(&successor),
+                call(env, &harness.farm, "unpause", vec![env])
+            ]
+        ),
+        Ok(())
+    );
+
+    let rejected = [
+        // Over the per-call limit
+        (clawback(101), Errors::AmountTooHigh),
+        // Handing the asset to anyone but the registered successor
+        (set_admin(&stranger), Errors::ContextDenied),
+        (
+            call(env, &harness.asset, "set_admin", vec![env]),
+            Errors::ContextDenied,
+        ),
+        // Allowlisted by name but not on the farm's asset
+        (
+            call(
+                env,
+                &stranger,
+                "clawback",
+                vec![env, farmer.into_val(env), 1i128.into_val(env)],
+            ),
+            Errors::ContextDenied,
+        ),
+        // Not allowlisted at all
+        (
+            call(
+                env,
+                &harness.asset,
+                "mint",
+                vec![env, farmer.into_val(env), 1i128.into_val(env)],
+            ),
+            Errors::ContextDenied,
+        ),
+        // A malformed amount
+        (
+            call(
+                env,
+                &harness.asset,
+                "clawback",
+                vec![env, farmer.into_val(env)],
+            ),
+            Errors::ContextDenied,
+        ),
+        // Deploying contracts
+        (
+            Context::CreateContractHostFn(CreateContractHostFnContext {
+                executable: ContractExecutable::Wasm(BytesN::from_array(env, &[0; 32])),
+                salt: BytesN::from_array(env, &[0; 32]),
+            }),
+            Errors::ContextDenied,
+        ),
+    ];
+
+    for (context, error) in rejected {
+        assert_eq!(
+            check_auth(&harness, core::slice::from_ref(&context)),
+            Err(Ok(error))
+        );
+
+        // One bad context fails the whole signature
+        assert_eq!(
+            check_auth(&harness, &[clawback(1), context]),
+            Err(Ok(error))
+        );
+    }
+}
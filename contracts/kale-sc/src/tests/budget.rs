@@ -0,0 +1,225 @@
+#![cfg(test)]
+
+extern crate std;
+
+use soroban_sdk::{
+    testutils::Address as _,
+    xdr::{LedgerEntryData, LedgerKey, Limits, ScAddress, ScVal, ToXdr, WriteXdr},
+    Address, Bytes, Env,
+};
+use stellar_strkey::{ed25519, Strkey};
+
+use crate::{
+    contract_farm::generate_farmer_key,
+    testutils::FarmHarness,
+    types::{Block, Storage},
+};
+
+// CPU instructions and memory bytes metered while running `f`
+fn budget_cost<T>(harness: &FarmHarness, f: impl FnOnce() -> T) -> (u64, u64) {
+    harness.env.cost_estimate().budget().reset_default();
+    f();
+
+    (
+        harness.env.cost_estimate().budget().cpu_instruction_cost(),
+        harness.env.cost_estimate().budget().memory_bytes_cost(),
+    )
+}
+
+// XDR size of the farm's instance entry, loaded by every call and rewritten by most
+fn instance_size(harness: &FarmHarness) -> usize {
+    let farm = ScAddress::from(&harness.farm);
+
+    harness
+        .env
+        .to_ledger_snapshot()
+        .ledger_entries
+        .into_iter()
+        .find_map(|(key, (entry, _))| match (*key, entry.data) {
+            (LedgerKey::ContractData(key), data @ LedgerEntryData::ContractData(_))
+                if key.contract == farm && key.key == ScVal::LedgerKeyContractInstance =>
+            {
+                Some(data.to_xdr(Limits::none()).unwrap().len())
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+// Puts the farm state back in instance storage, as farms deployed before the split hold it, the block in its legacy layout
+fn move_farm_state_to_instance(harness: &FarmHarness) {
+    let env = &harness.env;
+    let block: Block = harness.get(&Storage::FarmBlockV2).unwrap();
+
+    let index: u32 = harness.get(&Storage::FarmIndex).unwrap_or(0);
+
+    env.as_contract(&harness.farm, || {
+        env.storage().persistent().remove(&Storage::FarmIndex);
+        env.storage().persistent().remove(&Storage::FarmBlockV2);
+        env.storage().instance().set(&Storage::FarmIndex, &index);
+    });
+
+    harness.set_legacy_block(&Storage::FarmBlock, &block);
+}
+
+// How `work` derived the farmer key before it copied straight out of the XDR
+fn sliced_farmer_key(env: &Env, farmer: &Address) -> [u8; 32] {
+    let mut farmer_key = [0u8; 32];
+    let farmer_bytes = farmer.clone().to_xdr(env);
+
+    farmer_bytes
+        .slice(farmer_bytes.len() - 32..)
+        .copy_into_slice(&mut farmer_key);
+
+    farmer_key
+}
+
+#[test]
+fn test_work_cost() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let g_farmer = Address::from_string_bytes(&Bytes::from_slice(
+        env,
+        Strkey::PublicKeyEd25519(ed25519::PublicKey([7; 32]))
+            .to_string()
+            .as_bytes(),
+    ));
+    let c_farmer = Address::generate(env);
+
+    for farmer in [&g_farmer, &c_farmer] {
+        // Both run in the farm's context, the way `work` calls them
+        let key = |generate: fn(&Env, &Address) -> [u8; 32]| {
+            env.as_contract(&harness.farm, || generate(env, farmer))
+        };
+        let copied = budget_cost(&harness, || key(generate_farmer_key));
+        let sliced = budget_cost(&harness, || key(sliced_farmer_key));
+
+        assert_eq!(key(generate_farmer_key), key(sliced_farmer_key));
+        // Copying the key saves the slice's host object on every `work`
+        assert!(copied.0 < sliced.0, "{copied:?} vs {sliced:?}");
+        assert!(copied.1 < sliced.1, "{copied:?} vs {sliced:?}");
+
+        harness.plant(farmer, 0);
+    }
+
+    harness.advance_ledgers(1);
+
+    for farmer in [&g_farmer, &c_farmer] {
+        harness.work(farmer, 1);
+
+        let first = env.cost_estimate().resources();
+
+        harness.work(farmer, 12);
+
+        let resubmitted = env.cost_estimate().resources();
+
+        // Nothing is cached, so every resubmission pays for the key again, and only for that
+        assert_eq!(first.instructions, resubmitted.instructions);
+        assert_eq!(first.write_bytes, resubmitted.write_bytes);
+    }
+}
+
+// Write bytes, instructions and non-rent fee of a staked `plant` and its `work` on a fresh farm
+// A `legacy` farm has its state put back before each call, so both read it the way farms deployed before the split do
+fn plant_and_work_cost(legacy: bool) -> [(u32, i64, i64); 2] {
+    let harness = FarmHarness::new();
+    let farmer = harness.farmer(1_0000000);
+    let cost = || {
+        let resources = harness.env.cost_estimate().resources();
+        let fee = harness.env.cost_estimate().fee();
+
+        (
+            resources.write_bytes,
+            resources.instructions,
+            // rent is left out, moving the state into new persistent entries pays it once
+            fee.total - fee.persistent_entry_rent - fee.temporary_entry_rent,
+        )
+    };
+
+    harness.plant(&harness.farmer(0), 0);
+
+    if legacy {
+        move_farm_state_to_instance(&harness);
+    }
+
+    harness.plant(&farmer, 1_0000000);
+
+    let plant = cost();
+
+    if legacy {
+        move_farm_state_to_instance(&harness);
+    }
+
+    harness.advance_ledgers(1);
+    harness.work(&farmer, 1);
+
+    [plant, cost()]
+}
+
+#[test]
+fn test_farm_state_footprint() {
+    let split = plant_and_work_cost(false);
+    let legacy = plant_and_work_cost(true);
+
+    // `plant` and `work` read `FarmBlockV2` and `FarmIndex` as entries of their own, but no longer rewrite the instance
+    for (split, legacy) in split.iter().zip(legacy.iter()) {
+        assert!(split.0 < legacy.0, "{split:?} vs {legacy:?}");
+        assert!(split.1 < legacy.1, "{split:?} vs {legacy:?}");
+        assert!(split.2 < legacy.2, "{split:?} vs {legacy:?}");
+    }
+
+    let harness = FarmHarness::new();
+    let farmers = [harness.farmer(0), harness.farmer(0)];
+
+    for farmer in &farmers {
+        harness.plant(farmer, 0);
+        harness.work(farmer, 1);
+    }
+
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    let split_size = instance_size(&harness);
+    let split_cost = budget_cost(&harness, || harness.harvest(&farmers[0], 0));
+
+    move_farm_state_to_instance(&harness);
+
+    let legacy_size = instance_size(&harness);
+    let legacy_cost = budget_cost(&harness, || harness.harvest(&farmers[1], 0));
+
+    // Every call used to drag the whole `FarmBlock` along with the instance
+    assert!(
+        legacy_size > split_size + 200,
+        "{legacy_size} vs {split_size}"
+    );
+    assert!(
+        legacy_cost.0 > split_cost.0,
+        "{legacy_cost:?} vs {split_cost:?}"
+    );
+    assert!(
+        legacy_cost.1 > split_cost.1,
+        "{legacy_cost:?} vs {split_cost:?}"
+    );
+
+    // Legacy farms keep working and move each key out on its next write, `FarmIndex` when the next block opens
+    assert_eq!(harness.index(), 1);
+
+    harness.plant(&harness.farmer(0), 0);
+
+    assert!(instance_size(&harness) < legacy_size);
+
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    assert_eq!(instance_size(&harness), split_size);
+    assert_eq!(harness.index(), 2);
+    harness.env.as_contract(&harness.farm, || {
+        assert!(!harness.env.storage().instance().has(&Storage::FarmIndex));
+        assert!(!harness.env.storage().instance().has(&Storage::FarmBlock));
+        assert!(harness
+            .env
+            .storage()
+            .persistent()
+            .has(&Storage::FarmBlockV2));
+    });
+}
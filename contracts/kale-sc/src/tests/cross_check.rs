@@ -0,0 +1,250 @@
+#![cfg(test)]
+
+extern crate std;
+
+use ed25519_dalek::Keypair;
+use soroban_fixed_point_math::SorobanFixedPoint;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    Address, Bytes, Env,
+};
+use stellar_strkey::{ed25519, Strkey};
+
+use crate::{
+    contract_farm::generate_xdr_key,
+    testutils::{farmer_key, find_nonce_and_hash, FarmHarness},
+    types::{Block, HashAlgorithm, Normalization, Scaling},
+    BLOCK_INTERVAL, BLOCK_REWARD,
+};
+
+// Deterministic stand-in for random inputs so failures are reproducible
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn next_i128(&mut self, bits: u32) -> i128 {
+        let value = ((self.next() as u128) << 64 | self.next() as u128) >> (128 - bits);
+
+        value as i128
+    }
+
+    fn next_bytes(&mut self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next().to_be_bytes());
+        }
+
+        bytes
+    }
+}
+
+#[test]
+fn test_hash_matches_host() {
+    let env = Env::default();
+    let mut rng = XorShift(0x6b616c65);
+
+    for _ in 0..100 {
+        let preimage = kale_core::generate_preimage(
+            rng.next() as u32,
+            rng.next(),
+            &rng.next_bytes(),
+            &rng.next_bytes(),
+        );
+        let bytes = Bytes::from_array(&env, &preimage);
+
+        assert_eq!(
+            env.crypto().keccak256(&bytes).to_array(),
+            kale_core::generate_hash(kale_core::HashAlgorithm::Keccak256, &preimage)
+        );
+        assert_eq!(
+            env.crypto().sha256(&bytes).to_array(),
+            kale_core::generate_hash(kale_core::HashAlgorithm::Sha256, &preimage)
+        );
+    }
+}
+
+#[test]
+fn test_farmer_key_matches_strkey() {
+    let env = Env::default();
+
+    let keypair = Keypair::from_bytes(&[
+        149, 154, 40, 132, 13, 234, 167, 87, 182, 44, 152, 45, 242, 179, 187, 17, 139, 106, 49, 85,
+        249, 235, 17, 248, 24, 170, 19, 164, 23, 117, 145, 252, 172, 35, 170, 26, 69, 15, 75, 127,
+        192, 170, 166, 54, 68, 127, 218, 29, 130, 173, 159, 1, 253, 192, 48, 242, 80, 12, 55, 152,
+        223, 122, 198, 96,
+    ])
+    .unwrap();
+
+    let strkey = Strkey::PublicKeyEd25519(ed25519::PublicKey(keypair.public.to_bytes()));
+    let farmer =
+        Address::from_string_bytes(&Bytes::from_slice(&env, strkey.to_string().as_bytes()));
+
+    // Off-chain miners only have the G- address, its raw public key must be what `work` hashes
+    assert_eq!(farmer_key(&env, &farmer), keypair.public.to_bytes());
+
+    let farmer = Address::generate(&env);
+    let mut strkey = [0u8; 56];
+
+    farmer.to_string().copy_into_slice(&mut strkey);
+
+    let Strkey::Contract(contract) =
+        Strkey::from_string(core::str::from_utf8(&strkey).unwrap()).unwrap()
+    else {
+        panic!("expected a contract address");
+    };
+
+    assert_eq!(farmer_key(&env, &farmer), contract.0);
+
+    // Encodings at or past the copy buffer's size still end in the key
+    for length in [48, 64] {
+        let mut xdr = std::vec![0u8; length];
+
+        xdr[length - 32..].copy_from_slice(&contract.0);
+
+        assert_eq!(generate_xdr_key(&Bytes::from_slice(&env, &xdr)), contract.0);
+    }
+}
+
+#[test]
+fn test_mul_div_floor_matches_soroban() {
+    let env = Env::default();
+    let mut rng = XorShift(0x7061696c);
+
+    for i in 0..1000 {
+        // Alternate between products that fit in i128 and ones that need the 256 bit fallback
+        let bits = if i % 2 == 0 { 60 } else { 120 };
+        let sign = |rng: &mut XorShift| if rng.next().is_multiple_of(4) { -1 } else { 1 };
+
+        let x = rng.next_i128(bits) * sign(&mut rng);
+        let y = rng.next_i128(bits) * sign(&mut rng);
+        // Keep the quotient representable, soroban panics where kale-core returns None
+        let z = (x.abs().max(y.abs()) + rng.next_i128(bits)).max(1) * sign(&mut rng);
+
+        assert_eq!(
+            kale_core::mul_div_floor(x, y, z),
+            Some(x.fixed_mul_floor(&env, &y, &z)),
+            "{} * {} / {}",
+            x,
+            y,
+            z
+        );
+    }
+
+    assert_eq!(kale_core::mul_div_floor(i128::MAX, i128::MAX, 1), None);
+    assert_eq!(kale_core::mul_div_floor(1, 1, 0), None);
+}
+
+// The reward as the contract worked it out before the math moved into kale-core, linear scaling only
+fn baseline_reward(env: &Env, block: &Block, gap: u32, stake: i128, zeros: u32) -> i128 {
+    let normalization = &block.normalization;
+
+    let gap = gap.max(block.min_gap).min(block.max_gap) as i128;
+    let stake_value = stake.max(block.min_stake).min(block.max_stake);
+    let zeros = zeros.max(block.min_zeros).min(block.max_zeros) as i128;
+
+    let range_gap = (block.max_gap as i128 - block.min_gap as i128).max(1);
+    let range_stake = (block.max_stake - block.min_stake).max(1);
+    let range_zeros = (block.max_zeros as i128 - block.min_zeros as i128).max(1);
+
+    let max_range = range_gap.max(range_stake).max(range_zeros);
+    let min_threshold = (max_range / 100).max(1);
+
+    let normalized_gap = (gap - block.min_gap as i128)
+        .fixed_mul_floor(env, &max_range, &range_gap)
+        .max(min_threshold)
+        * normalization.gap_weight as i128;
+    let normalized_stake = (stake_value - block.min_stake)
+        .fixed_mul_floor(env, &max_range, &range_stake)
+        .max(min_threshold)
+        * normalization.stake_weight as i128;
+    let normalized_zeros = (zeros - block.min_zeros as i128)
+        .fixed_mul_floor(env, &max_range, &range_zeros)
+        .max(min_threshold)
+        * normalization.zeros_weight as i128;
+
+    (normalized_gap + normalized_stake + normalized_zeros).fixed_mul_floor(
+        env,
+        &(BLOCK_REWARD + block.staked_total),
+        &block.normalized_total.max(1),
+    ) + stake
+}
+
+#[test]
+fn test_reward_matches_contract() {
+    let harness = FarmHarness::new();
+    let env = harness.env.clone();
+    let farm_client = &harness.farm_client;
+
+    // Uneven weights so each component has to be weighted the way it was
+    farm_client.set_normalization(&Normalization {
+        gap: Scaling::Linear,
+        stake: Scaling::Linear,
+        zeros: Scaling::Linear,
+        gap_weight: 2,
+        stake_weight: 1,
+        zeros_weight: 3,
+    });
+
+    let farmers: [Address; 4] = core::array::from_fn(|_| Address::generate(&env));
+    let stakes = [0, 1000, 1_0000000, 250_0000000];
+
+    for (farmer, stake) in farmers.iter().zip(stakes) {
+        harness.mint(farmer, stake * 2);
+    }
+
+    for index in 0..2u32 {
+        let sequence = env.ledger().sequence();
+
+        for (farmer, stake) in farmers.iter().zip(stakes) {
+            farm_client.plant(farmer, &stake);
+        }
+
+        let block = harness.block(index).unwrap();
+
+        for (i, farmer) in farmers.iter().enumerate() {
+            let (nonce, hash) = find_nonce_and_hash(
+                &env,
+                &HashAlgorithm::Keccak256,
+                &index,
+                &block.entropy,
+                farmer,
+                i as u32 * 3,
+            );
+
+            env.ledger()
+                .set_sequence_number(sequence + 1 + i as u32 * 5);
+
+            farm_client.work(farmer, &hash, &nonce);
+        }
+
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + BLOCK_INTERVAL);
+    }
+
+    // Close the second block
+    farm_client.plant(&farmers[0], &0);
+
+    let block = harness.block(1).unwrap();
+    let pails = farmers
+        .clone()
+        .map(|farmer| harness.pail(&farmer, 1).unwrap());
+
+    for (farmer, pail) in farmers.iter().zip(pails) {
+        let reward = baseline_reward(
+            &env,
+            &block,
+            pail.gap.unwrap(),
+            pail.stake,
+            pail.zeros.unwrap(),
+        );
+
+        assert_eq!(farm_client.harvest(farmer, &1), reward);
+    }
+}
@@ -1,2 +1,6 @@
+mod auth;
+mod budget;
+mod cross_check;
+mod multisig;
 mod test;
 mod utils;
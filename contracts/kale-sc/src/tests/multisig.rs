@@ -0,0 +1,276 @@
+#![cfg(test)]
+
+extern crate std;
+
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use soroban_sdk::{
+    testutils::{MockAuth, MockAuthInvoke},
+    vec,
+    xdr::{
+        Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization, InvokeContractArgs, Limits,
+        ScAddress, ScVal, SorobanAddressCredentials, SorobanAuthorizationEntry,
+        SorobanAuthorizedFunction, SorobanAuthorizedInvocation, SorobanCredentials, WriteXdr,
+    },
+    Bytes, BytesN, Env, IntoVal, InvokeError, TryFromVal, Vec,
+};
+
+use crate::{
+    errors::Errors,
+    testutils::FarmHarness,
+    types::{Multisig, Signature, Storage},
+};
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = PublicKey::from(&secret);
+
+    Keypair { secret, public }
+}
+
+// Keypairs from fixed seeds, ordered by public key as the farm expects them
+fn keypairs(count: u8) -> std::vec::Vec<Keypair> {
+    let mut keypairs: std::vec::Vec<Keypair> = (1..=count).map(keypair).collect();
+
+    keypairs.sort_by_key(|keypair| keypair.public.to_bytes());
+    keypairs
+}
+
+fn public_key(env: &Env, keypair: &Keypair) -> BytesN<32> {
+    BytesN::from_array(env, &keypair.public.to_bytes())
+}
+
+fn sign(env: &Env, keypairs: &[&Keypair], payload: &[u8; 32]) -> Vec<Signature> {
+    let mut signatures = Vec::new(env);
+
+    for keypair in keypairs {
+        signatures.push_back(Signature {
+            public_key: public_key(env, keypair),
+            signature: BytesN::from_array(env, &keypair.sign(payload).to_bytes()),
+        });
+    }
+
+    signatures
+}
+
+fn multisig(env: &Env, keypairs: &[Keypair], threshold: u32) -> Multisig {
+    let mut signers = Vec::new(env);
+
+    for keypair in keypairs {
+        signers.push_back(public_key(env, keypair));
+    }
+
+    Multisig { signers, threshold }
+}
+
+fn check_auth(
+    harness: &FarmHarness,
+    payload: &[u8; 32],
+    signatures: Option<Vec<Signature>>,
+) -> Result<(), Result<Errors, InvokeError>> {
+    let env = &harness.env;
+
+    env.try_invoke_contract_check_auth::<Errors>(
+        &harness.farm,
+        &BytesN::from_array(env, payload),
+        signatures.into_val(env),
+        &vec![env],
+    )
+}
+
+// A real auth entry for `fn_name` on the farm, signed by `keypairs` on the farm's behalf
+fn authorize(
+    harness: &FarmHarness,
+    keypairs: &[&Keypair],
+    fn_name: &str,
+    nonce: i64,
+) -> SorobanAuthorizationEntry {
+    let env = &harness.env;
+    let signature_expiration_ledger = env.ledger().sequence() + 100;
+
+    let invocation = SorobanAuthorizedInvocation {
+        function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+            contract_address: ScAddress::from(&harness.farm),
+            function_name: fn_name.try_into().unwrap(),
+            args: Default::default(),
+        }),
+        sub_invocations: Default::default(),
+    };
+
+    let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: Hash(env.ledger().network_id().to_array()),
+        nonce,
+        signature_expiration_ledger,
+        invocation: invocation.clone(),
+    });
+    let preimage = Bytes::from_slice(env, &preimage.to_xdr(Limits::none()).unwrap());
+    let payload = env.crypto().sha256(&preimage).to_array();
+
+    let signatures = sign(env, keypairs, &payload);
+
+    SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: ScAddress::from(&harness.farm),
+            nonce,
+            signature_expiration_ledger,
+            signature: ScVal::try_from_val(env, &signatures.to_val()).unwrap(),
+        }),
+        root_invocation: invocation,
+    }
+}
+
+#[test]
+fn test_set_signers() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let keypairs = keypairs(3);
+
+    // No signer set to adjust yet
+    let err = farm_client.try_set_threshold(&1).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::SignersInvalid.into());
+
+    let mut unsorted = multisig(env, &keypairs[1..], 2);
+    unsorted.signers.push_back(public_key(env, &keypairs[0]));
+
+    let mut duplicated = multisig(env, &keypairs[..2], 2);
+    duplicated.signers.push_back(public_key(env, &keypairs[1]));
+
+    for invalid in [
+        multisig(env, &keypairs, 0),
+        multisig(env, &keypairs, 4),
+        multisig(env, &[], 0),
+        unsorted,
+        duplicated,
+    ] {
+        let err = farm_client.try_set_signers(&invalid).unwrap_err().unwrap();
+
+        assert_eq!(err, Errors::SignersInvalid.into());
+    }
+
+    farm_client.set_signers(&multisig(env, &keypairs, 2));
+
+    // The homesteader's key alone no longer passes for the homestead
+    env.mock_auths(&[MockAuth {
+        address: &harness.homesteader,
+        invoke: &MockAuthInvoke {
+            contract: &harness.farm,
+            fn_name: "pause",
+            args: vec![env],
+            sub_invokes: &[],
+        },
+    }]);
+
+    assert!(farm_client.try_pause().is_err());
+
+    env.mock_all_auths();
+
+    farm_client.set_threshold(&3);
+
+    assert_eq!(env.auths()[0].0, harness.farm);
+
+    let err = farm_client.try_set_threshold(&4).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::SignersInvalid.into());
+
+    // Rotating to a single new key
+    let rotated = &[keypair(9)];
+
+    farm_client.set_signers(&multisig(env, rotated, 1));
+
+    assert_eq!(
+        harness.get::<Multisig>(&Storage::HomesteadSigners),
+        Some(multisig(env, rotated, 1))
+    );
+}
+
+#[test]
+fn test_check_auth_multisig() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+
+    let keypairs = keypairs(3);
+    let stranger = &keypair(9);
+    let payload = [7; 32];
+
+    harness
+        .farm_client
+        .set_signers(&multisig(env, &keypairs, 2));
+
+    for signers in [
+        [&keypairs[0], &keypairs[1]],
+        [&keypairs[0], &keypairs[2]],
+        [&keypairs[1], &keypairs[2]],
+    ] {
+        assert_eq!(
+            check_auth(&harness, &payload, Some(sign(env, &signers, &payload))),
+            Ok(())
+        );
+    }
+
+    assert_eq!(
+        check_auth(
+            &harness,
+            &payload,
+            Some(sign(
+                env,
+                &[&keypairs[0], &keypairs[1], &keypairs[2]],
+                &payload
+            ))
+        ),
+        Ok(())
+    );
+
+    let rejected = [
+        // Below the threshold
+        None,
+        Some(sign(env, &[&keypairs[0]], &payload)),
+        // One signer twice, or out of order
+        Some(sign(env, &[&keypairs[0], &keypairs[0]], &payload)),
+        Some(sign(env, &[&keypairs[1], &keypairs[0]], &payload)),
+        // Not in the signer set
+        Some(sign(env, &[&keypairs[0], stranger], &payload)),
+    ];
+
+    for signatures in rejected {
+        assert_eq!(
+            check_auth(&harness, &payload, signatures),
+            Err(Ok(Errors::SignaturesInvalid))
+        );
+    }
+
+    // Signed over something else
+    assert!(check_auth(
+        &harness,
+        &payload,
+        Some(sign(env, &[&keypairs[0], &keypairs[1]], &[8; 32]))
+    )
+    .is_err());
+}
+
+#[test]
+fn test_multisig_admin() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let keypairs = keypairs(3);
+
+    farm_client.set_signers(&multisig(env, &keypairs, 2));
+
+    env.set_auths(&[authorize(&harness, &[&keypairs[0]], "pause", 1)]);
+
+    assert!(farm_client.try_pause().is_err());
+
+    env.set_auths(&[authorize(
+        &harness,
+        &[&keypairs[0], &keypairs[2]],
+        "pause",
+        2,
+    )]);
+
+    farm_client.pause();
+
+    assert!(harness.get::<bool>(&Storage::FarmPaused).unwrap());
+}
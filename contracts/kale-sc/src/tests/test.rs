@@ -1,50 +1,36 @@
 #![cfg(test)]
 
-use std::{print, println};
+use std::println;
 extern crate std;
 
 use crate::{
     errors::Errors,
-    tests::utils::find_nonce_and_hash,
-    types::{Block, Storage},
-    Contract, ContractClient, BLOCK_INTERVAL,
+    testutils::{find_nonce_and_hash, FarmHarness},
+    types::{
+        AssetMode, Block, Difficulty, FeeSchedule, Greenhouse, HashAlgorithm, Normalization, Pail,
+        Reward, Scaling, Storage, Vesting, VestingSchedule,
+    },
+    BLOCK_INTERVAL, BLOCK_REWARD, BLOCK_SUMMARIES, SEASON_LEADERS, SUNSET_WINDOW,
 };
 use soroban_sdk::{
-    testutils::{Address as _, EnvTestConfig, Ledger},
-    token, Address, BytesN, Env,
+    testutils::{Address as _, Events, Ledger},
+    token, vec, Address, BytesN, Symbol, TryFromVal,
 };
 
 // TODO add more tests
 // TODO write test utils
 
 #[test]
+#[allow(clippy::zero_prefixed_literal)]
 fn test() {
-    let mut env: Env = Env::default();
-
-    env.set_config(EnvTestConfig {
-        capture_snapshot_at_drop: false,
-    });
-
-    env.ledger().set_min_temp_entry_ttl(17280);
-    env.ledger().set_min_persistent_entry_ttl(2073600);
-
-    env.mock_all_auths();
+    let harness = FarmHarness::new();
+    let env = harness.env.clone();
+    let farm_address = harness.farm.clone();
+    let farm_client = &harness.farm_client;
 
     let sequence = env.ledger().sequence();
     let timestamp = env.ledger().timestamp();
 
-    let homesteader: Address = Address::generate(&env);
-
-    let asset_sac = env.register_stellar_asset_contract_v2(homesteader.clone());
-    let asset_address = asset_sac.address();
-    let asset_homesteader = token::StellarAssetClient::new(&env, &asset_address);
-    let asset_client = token::Client::new(&env, &asset_address);
-
-    let farm_address: Address = env.register(Contract, (&homesteader, &asset_address));
-    let farm_client = ContractClient::new(&env, &farm_address);
-
-    asset_homesteader.set_admin(&farm_address);
-
     let farmer_1: Address = Address::generate(&env);
     let farmer_2: Address = Address::generate(&env);
     let farmer_3: Address = Address::generate(&env);
@@ -55,10 +41,10 @@ fn test() {
     let amount_3 = 0;
     let amount_4 = 0_1000000;
 
-    asset_homesteader.mint(&farmer_1, &amount_1);
-    asset_homesteader.mint(&farmer_2, &amount_2);
-    asset_homesteader.mint(&farmer_3, &amount_3);
-    asset_homesteader.mint(&farmer_4, &amount_4);
+    harness.mint(&farmer_1, amount_1);
+    harness.mint(&farmer_2, amount_2);
+    harness.mint(&farmer_3, amount_3);
+    harness.mint(&farmer_4, amount_4);
 
     farm_client.plant(&farmer_1, &amount_1);
     farm_client.plant(&farmer_2, &amount_2);
@@ -71,21 +57,22 @@ fn test() {
     env.as_contract(&farm_address, || {
         index = env
             .storage()
-            .instance()
+            .persistent()
             .get::<Storage, u32>(&Storage::FarmIndex);
         block = env
             .storage()
             .temporary()
-            .get::<Storage, Block>(&Storage::Block(index.unwrap_or(0)));
+            .get::<Storage, Block>(&Storage::BlockV2(index.unwrap_or(0)));
     });
 
     let index = index.unwrap_or(0);
     let block = block.unwrap();
 
     println!("{} {:?}", index, block);
-    print!("\n");
+    println!();
 
-    let (nonce_0, hash_0) = find_nonce_and_hash(&env, &index, &block.entropy, &farmer_1, 0);
+    let (nonce_0, hash_0) =
+        find_nonce_and_hash(&env, &block.algorithm, &index, &block.entropy, &farmer_1, 0);
 
     let (nonce_1, hash_1) = (
         26428569u64,
@@ -131,7 +118,7 @@ fn test() {
     env.ledger().set_sequence_number(sequence + 1);
 
     farm_client.work(&farmer_1, &hash_0, &nonce_0); // 0 zeros
-    farm_client.work(&farmer_4, &hash_4, &nonce_4); // 9 zeros
+    farm_client.work(&farmer_4, &hash_4, &nonce_4); // 39 zeros
 
     // Should not be able to update for a lower or equal zero count
     let err = farm_client
@@ -142,22 +129,22 @@ fn test() {
     assert_eq!(err, Errors::ZeroCountTooLow.into());
 
     // Should be able to update for a higher zero count
-    farm_client.work(&farmer_1, &hash_1, &nonce_1); // 6 zeros
+    farm_client.work(&farmer_1, &hash_1, &nonce_1); // 24 zeros
 
     env.ledger().set_sequence_number(sequence + 20);
 
-    farm_client.work(&farmer_2, &hash_2, &nonce_2); // 7 zeros
-    farm_client.work(&farmer_3, &hash_3, &nonce_3); // 8 zeros
+    farm_client.work(&farmer_2, &hash_2, &nonce_2); // 30 zeros
+    farm_client.work(&farmer_3, &hash_3, &nonce_3); // 33 zeros
 
     env.as_contract(&farm_address, || {
         let block = env
             .storage()
             .temporary()
-            .get::<Storage, Block>(&Storage::Block(index))
+            .get::<Storage, Block>(&Storage::BlockV2(index))
             .unwrap();
 
         println!("{} {:?}", index, block);
-        print!("\n");
+        println!();
     });
 
     env.ledger().set_timestamp(timestamp + BLOCK_INTERVAL);
@@ -171,36 +158,1804 @@ fn test() {
 
     println!(
         "farmer 1 profit: {:?}",
-        asset_client.balance(&farmer_1) - amount_1
+        harness.balance(&farmer_1) - amount_1
     );
     println!(
         "farmer 2 profit: {:?}",
-        asset_client.balance(&farmer_2) - amount_2
+        harness.balance(&farmer_2) - amount_2
     );
     println!(
         "farmer 3 profit: {:?}",
-        asset_client.balance(&farmer_3) - amount_3
+        harness.balance(&farmer_3) - amount_3
     );
     println!(
         "farmer 4 profit: {:?}",
-        asset_client.balance(&farmer_4) - amount_4
+        harness.balance(&farmer_4) - amount_4
     );
-    print!("\n");
+    println!();
 
     env.as_contract(&farm_address, || {
         let index = env
             .storage()
-            .instance()
+            .persistent()
             .get::<Storage, u32>(&Storage::FarmIndex)
             .unwrap();
 
         let block = env
             .storage()
             .temporary()
-            .get::<Storage, Block>(&Storage::Block(index))
+            .get::<Storage, Block>(&Storage::BlockV2(index))
             .unwrap();
 
         println!("{:?}", index);
         println!("{:?}", block);
     });
 }
+
+#[test]
+fn test_schedule_algorithm() {
+    let harness = FarmHarness::new();
+    let env = harness.env.clone();
+    let farm_client = &harness.farm_client;
+
+    let sequence = env.ledger().sequence();
+    let timestamp = env.ledger().timestamp();
+
+    let farmer: Address = Address::generate(&env);
+
+    farm_client.plant(&farmer, &0);
+
+    // The current block can't be switched out from under its farmers
+    let err = farm_client
+        .try_schedule_algorithm(&0, &HashAlgorithm::Sha256)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::ScheduleInvalid.into());
+
+    farm_client.schedule_algorithm(&1, &HashAlgorithm::Sha256);
+
+    let get_block = |index: u32| harness.block(index).unwrap();
+
+    let block = get_block(0);
+
+    assert_eq!(block.algorithm, HashAlgorithm::Keccak256);
+
+    env.ledger().set_timestamp(timestamp + BLOCK_INTERVAL);
+    env.ledger().set_sequence_number(sequence + 1);
+
+    farm_client.plant(&farmer, &0);
+
+    let block = get_block(1);
+
+    assert_eq!(block.algorithm, HashAlgorithm::Sha256);
+
+    env.ledger().set_sequence_number(sequence + 2);
+
+    // A keccak256 hash no longer satisfies the block
+    let (nonce, hash) = find_nonce_and_hash(
+        &env,
+        &HashAlgorithm::Keccak256,
+        &1,
+        &block.entropy,
+        &farmer,
+        4,
+    );
+    let err = farm_client
+        .try_work(&farmer, &hash, &nonce)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::HashInvalid.into());
+
+    let (nonce, hash) = find_nonce_and_hash(&env, &block.algorithm, &1, &block.entropy, &farmer, 4);
+
+    farm_client.work(&farmer, &hash, &nonce);
+}
+
+#[test]
+fn test_difficulty() {
+    let harness = FarmHarness::new();
+    let env = harness.env.clone();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1: Address = Address::generate(&env);
+    let farmer_2: Address = Address::generate(&env);
+
+    // A block opened before bit counting, exactly as the farm stored it back then
+    let block = Block {
+        timestamp: env.ledger().timestamp(),
+        min_gap: 0,
+        min_stake: 0,
+        min_zeros: 0,
+        max_gap: 0,
+        max_stake: 0,
+        max_zeros: 0,
+        entropy: BytesN::from_array(&env, &[0; 32]),
+        staked_total: 0,
+        normalized_total: 0,
+        algorithm: HashAlgorithm::Keccak256,
+        difficulty: Difficulty::Nibbles,
+        normalization: Normalization::default(),
+        pails: u32::MAX,
+        fee: None,
+    };
+
+    harness.set_legacy_block(&Storage::Block(0), &block);
+    harness.set_legacy_block(
+        &Storage::FarmBlock,
+        &Block {
+            min_gap: u32::MAX,
+            min_stake: i128::MAX,
+            min_zeros: u32::MAX,
+            max_stake: i128::MIN,
+            ..block.clone()
+        },
+    );
+
+    farm_client.plant(&farmer_1, &0);
+    farm_client.plant(&farmer_2, &0);
+
+    assert_eq!(harness.block(0).unwrap().difficulty, Difficulty::Nibbles);
+
+    let get_zeros =
+        |farmer: &Address, index: u32| harness.pail(farmer, index).unwrap().zeros.unwrap();
+
+    harness.advance_ledgers(1);
+
+    let (nonce_1, hash_1) = harness.mine(&farmer_1, 10);
+
+    farm_client.work(&farmer_1, &hash_1, &nonce_1);
+
+    let bits_1 = kale_core::count_zeros(&hash_1.to_array(), kale_core::Difficulty::Bits);
+
+    assert_eq!(get_zeros(&farmer_1, 0), bits_1 / 4);
+
+    harness.next_block();
+    farm_client.plant(&farmer_1, &0);
+    farm_client.plant(&farmer_2, &0);
+
+    // The next block counts bits and doesn't inherit the nibble counted zero bounds
+    let block = harness.block(1).unwrap();
+
+    assert_eq!(block.difficulty, Difficulty::Bits);
+    assert_eq!((block.min_zeros, block.max_zeros), (0, 0));
+
+    harness.advance_ledgers(1);
+
+    let (nonce_2, hash_2) = harness.mine(&farmer_2, 10);
+
+    farm_client.work(&farmer_2, &hash_2, &nonce_2);
+
+    let bits_2 = kale_core::count_zeros(&hash_2.to_array(), kale_core::Difficulty::Bits);
+
+    assert!(bits_2 >= 10);
+    assert_eq!(get_zeros(&farmer_2, 1), bits_2);
+
+    // Only now do the zero bounds carry over, on the same scale
+    harness.next_block();
+    farm_client.plant(&farmer_1, &0);
+
+    let block = harness.block(2).unwrap();
+
+    assert_eq!((block.min_zeros, block.max_zeros), (bits_2, bits_2));
+}
+
+#[test]
+fn test_normalization() {
+    let linear = harvest_with_normalization(&Normalization::default());
+    let sqrt_stake = harvest_with_normalization(&Normalization {
+        stake: Scaling::Sqrt,
+        ..Normalization::default()
+    });
+    let log2_zeros = harvest_with_normalization(&Normalization {
+        zeros: Scaling::Log2,
+        ..Normalization::default()
+    });
+    let stake_weighted = harvest_with_normalization(&Normalization {
+        stake_weight: 3,
+        zeros_weight: 0,
+        ..Normalization::default()
+    });
+
+    println!("linear:         {:?}", linear);
+    println!("sqrt stake:     {:?}", sqrt_stake);
+    println!("log2 zeros:     {:?}", log2_zeros);
+    println!("stake weighted: {:?}", stake_weighted);
+
+    for profits in [&linear, &sqrt_stake, &log2_zeros, &stake_weighted] {
+        assert!(profits.iter().sum::<i128>() <= BLOCK_REWARD);
+    }
+
+    let [whale, hasher, patient] = [0, 1, 2];
+
+    // Diminishing returns on stake moves reward from the whale to small stakers
+    assert!(sqrt_stake[whale] < linear[whale]);
+    assert!(sqrt_stake[patient] > linear[patient]);
+
+    // A middling zero count is worth relatively more on a log curve
+    assert!(log2_zeros[patient] > linear[patient]);
+
+    // Dropping the zeros weight takes hashing power out of the reward, the weight applies after the threshold
+    assert!(stake_weighted[whale] > linear[whale]);
+    assert!(stake_weighted[hasher] < linear[hasher]);
+
+    let harness = FarmHarness::new();
+    let farmer = harness.farmer(0);
+
+    harness.farm_client.set_normalization(&Normalization {
+        zeros_weight: 0,
+        ..Normalization::default()
+    });
+    harness.plant(&farmer, 0);
+    harness.advance_ledgers(1);
+    harness.work(&farmer, 16);
+
+    let reward = harness.farm_client.estimate_reward(&farmer, &0);
+
+    assert_eq!(reward.normalized_zeros, 0);
+    assert!(reward.normalized_gap > 0);
+}
+
+#[test]
+fn test_normalization_invalid() {
+    let harness = FarmHarness::new();
+
+    let err = harness
+        .farm_client
+        .try_set_normalization(&Normalization {
+            gap_weight: 0,
+            stake_weight: 0,
+            zeros_weight: 0,
+            ..Normalization::default()
+        })
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::NormalizationInvalid.into());
+}
+
+// Runs two identical blocks (the first only seeds the min/max bounds) and returns each farmer's profit from the second
+fn harvest_with_normalization(normalization: &Normalization) -> [i128; 3] {
+    let harness = FarmHarness::new();
+    let env = harness.env.clone();
+    let farm_client = &harness.farm_client;
+
+    farm_client.set_normalization(normalization);
+
+    // (stake, zeros, ledgers waited before `work`)
+    let strategies: [(i128, u32, u32); 3] = [
+        (1000_0000000, 4, 1), // whale
+        (0, 16, 1),           // hasher
+        (1_0000000, 8, 10),   // patient
+    ];
+    let farmers: [Address; 3] = core::array::from_fn(|_| Address::generate(&env));
+
+    for (farmer, (stake, ..)) in farmers.iter().zip(strategies) {
+        harness.mint(farmer, stake * 2);
+    }
+
+    let mut profits = [0; 3];
+
+    for index in 0..2u32 {
+        let sequence = env.ledger().sequence();
+
+        for (farmer, (stake, ..)) in farmers.iter().zip(strategies) {
+            farm_client.plant(farmer, &stake);
+        }
+
+        let block = harness.block(index).unwrap();
+
+        for (farmer, (_, zeros, wait)) in farmers.iter().zip(strategies) {
+            let (nonce, hash) = find_nonce_and_hash(
+                &env,
+                &block.algorithm,
+                &index,
+                &block.entropy,
+                farmer,
+                zeros,
+            );
+
+            env.ledger().set_sequence_number(sequence + wait);
+
+            farm_client.work(farmer, &hash, &nonce);
+        }
+
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + BLOCK_INTERVAL);
+        env.ledger().set_sequence_number(sequence + 60);
+    }
+
+    // Close the second block
+    farm_client.plant(&Address::generate(&env), &0);
+
+    for (i, (farmer, (stake, ..))) in farmers.iter().zip(strategies).enumerate() {
+        profits[i] = farm_client.harvest(farmer, &1) - stake;
+    }
+
+    profits
+}
+
+#[test]
+fn test_reward_estimates() {
+    let harness = FarmHarness::new();
+    let env = harness.env.clone();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1: Address = Address::generate(&env);
+    let farmer_2: Address = Address::generate(&env);
+
+    harness.mint(&farmer_1, 2_0000000);
+
+    // The first block only seeds the min/max bounds for the second
+    for index in 0..2u32 {
+        let sequence = env.ledger().sequence();
+
+        farm_client.plant(&farmer_1, &1_0000000);
+        farm_client.plant(&farmer_2, &0);
+
+        let block = harness.block(index).unwrap();
+
+        let (nonce_1, hash_1) =
+            find_nonce_and_hash(&env, &block.algorithm, &index, &block.entropy, &farmer_1, 4);
+        let (nonce_2, hash_2) = find_nonce_and_hash(
+            &env,
+            &block.algorithm,
+            &index,
+            &block.entropy,
+            &farmer_2,
+            12,
+        );
+
+        env.ledger().set_sequence_number(sequence + 2);
+
+        farm_client.work(&farmer_1, &hash_1, &nonce_1);
+
+        // Nothing to estimate before `work`
+        let err = farm_client
+            .try_estimate_reward(&farmer_2, &index)
+            .unwrap_err()
+            .unwrap();
+
+        assert_eq!(err, Errors::WorkMissing.into());
+
+        env.ledger().set_sequence_number(sequence + 8);
+
+        let zeros_2 = kale_core::count_zeros(&hash_2.to_array(), kale_core::Difficulty::Bits);
+
+        let preview = farm_client.preview_reward(&index, &Some(farmer_2.clone()), &0, &8, &zeros_2);
+
+        farm_client.work(&farmer_2, &hash_2, &nonce_2);
+
+        let estimate = farm_client.estimate_reward(&farmer_2, &index);
+
+        assert_eq!(preview, estimate);
+
+        // A resubmission's preview replaces the farmer's current work rather than adding to it
+        let (nonce_1, hash_1) =
+            find_nonce_and_hash(&env, &block.algorithm, &index, &block.entropy, &farmer_1, 8);
+        let zeros_1 = kale_core::count_zeros(&hash_1.to_array(), kale_core::Difficulty::Bits);
+
+        let preview =
+            farm_client.preview_reward(&index, &Some(farmer_1.clone()), &1_0000000, &8, &zeros_1);
+        let stacked = farm_client.preview_reward(&index, &None, &1_0000000, &8, &zeros_1);
+
+        farm_client.work(&farmer_1, &hash_1, &nonce_1);
+
+        assert_eq!(preview, farm_client.estimate_reward(&farmer_1, &index));
+        assert!(stacked.reward < preview.reward);
+
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + BLOCK_INTERVAL);
+    }
+
+    let Reward {
+        reward,
+        normalized_gap,
+        normalized_stake,
+        normalized_zeros,
+    } = farm_client.estimate_reward(&farmer_1, &1);
+
+    assert!(normalized_gap > 0 && normalized_stake > 0 && normalized_zeros > 0);
+
+    let estimate_2 = farm_client.estimate_reward(&farmer_2, &1);
+
+    // Close the second block
+    farm_client.plant(&farmer_2, &0);
+
+    assert_eq!(farm_client.harvest(&farmer_1, &1), reward);
+    assert_eq!(farm_client.harvest(&farmer_2, &1), estimate_2.reward);
+}
+
+#[test]
+fn test_greenhouse() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(1_0000000);
+    let farmer_3 = harness.farmer(0);
+    let farmer_4 = harness.farmer(0);
+    let idler = harness.farmer(0);
+
+    for farmer in [&farmer_1, &farmer_2, &farmer_3, &farmer_4] {
+        harness.plant(farmer, harness.balance(farmer));
+        harness.work(farmer, 4);
+    }
+
+    harness.plant(&idler, 0);
+
+    // Only a closed block's totals are final
+    let err = farm_client
+        .try_greenhouse(&farmer_1, &0)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::HarvestNotReady.into());
+
+    harness.next_block();
+    harness.plant(&farmer_3, 0);
+
+    let err = farm_client.try_greenhouse(&idler, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::WorkMissing.into());
+
+    let estimate_1 = farm_client.estimate_reward(&farmer_1, &0);
+    let estimate_2 = farm_client.estimate_reward(&farmer_2, &0);
+    let estimate_3 = farm_client.estimate_reward(&farmer_3, &0);
+
+    farm_client.greenhouse(&farmer_1, &0);
+    farm_client.greenhouse(&farmer_3, &0);
+
+    assert!(harness.pail(&farmer_1, 0).is_none());
+    assert_eq!(
+        harness
+            .get::<Greenhouse>(&Storage::Greenhouse(0))
+            .unwrap()
+            .pails,
+        2
+    );
+
+    // Let the temporary block rot and its summary age out of the ring, greenhoused pails harvest from their copy
+    evict_block(&harness, 0);
+    expire_block_summary(&harness, 0);
+
+    assert_eq!(farm_client.estimate_reward(&farmer_1, &0), estimate_1);
+    assert_eq!(farm_client.harvest(&farmer_1, &0), estimate_1.reward);
+
+    // Anyone else still holding a pail can lean on the copy while it's there
+    assert_eq!(farm_client.harvest(&farmer_2, &0), estimate_2.reward);
+
+    let err = farm_client.try_harvest(&farmer_1, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::PailMissing.into());
+
+    assert_eq!(farm_client.harvest(&farmer_3, &0), estimate_3.reward);
+
+    // The last greenhoused pail out takes the block copy with it
+    assert!(harness.get::<Greenhouse>(&Storage::Greenhouse(0)).is_none());
+    assert!(harness
+        .get::<Pail>(&Storage::GreenhousePail(farmer_3.clone(), 0))
+        .is_none());
+
+    let err = farm_client.try_harvest(&farmer_4, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::BlockMissing.into());
+}
+
+// Drops the temporary block the way its TTL running out would
+fn evict_block(harness: &FarmHarness, index: u32) {
+    harness.env.as_contract(&harness.farm, || {
+        harness
+            .env
+            .storage()
+            .temporary()
+            .remove(&Storage::BlockV2(index))
+    });
+}
+
+// Drops the temporary pail the way its TTL running out would
+fn evict_pail(harness: &FarmHarness, farmer: &Address, index: u32) {
+    harness.env.as_contract(&harness.farm, || {
+        harness
+            .env
+            .storage()
+            .temporary()
+            .remove(&Storage::Pail(farmer.clone(), index))
+    });
+}
+
+// Overwrites the summary's ring slot the way the block `BLOCK_SUMMARIES` later would
+fn expire_block_summary(harness: &FarmHarness, index: u32) {
+    let mut summary = harness.farm_client.block_summary(&index).unwrap();
+
+    summary.index += BLOCK_SUMMARIES;
+
+    harness.set(&Storage::BlockSummary(index % BLOCK_SUMMARIES), &summary);
+}
+
+#[test]
+fn test_block_summary() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(0);
+    let farmer_3 = harness.farmer(1_0000000);
+
+    for farmer in [&farmer_1, &farmer_2] {
+        harness.plant(farmer, harness.balance(farmer));
+        harness.work(farmer, 4);
+    }
+
+    // A forfeited stake stays in the pool
+    harness.plant(&farmer_3, 1_0000000);
+
+    assert_eq!(farm_client.block_summary(&0), None);
+
+    let block = harness.block(0).unwrap();
+    let estimate_1 = farm_client.estimate_reward(&farmer_1, &0);
+
+    harness.next_block();
+    harness.plant(&farmer_3, 0);
+
+    let summary = farm_client.block_summary(&0).unwrap();
+
+    assert_eq!(summary.index, 0);
+    assert_eq!(summary.normalized_total, block.normalized_total);
+    assert_eq!(summary.reward_pool, BLOCK_REWARD + 1_0000000);
+    assert_eq!(
+        (summary.min_gap, summary.max_gap),
+        (block.min_gap, block.max_gap)
+    );
+    assert_eq!(summary.normalization, block.normalization);
+    assert_eq!(farm_client.block_summary(&1), None);
+
+    // A closed block is read from its summary even while the temporary entry is still around
+    harness.set(
+        &Storage::BlockV2(0),
+        &Block {
+            staked_total: block.staked_total + 5_0000000,
+            ..harness.block(0).unwrap()
+        },
+    );
+
+    assert_eq!(farm_client.estimate_reward(&farmer_1, &0), estimate_1);
+
+    // Harvest falls back to the summary once the temporary block is gone
+    evict_block(&harness, 0);
+
+    assert_eq!(farm_client.estimate_reward(&farmer_1, &0), estimate_1);
+    assert_eq!(farm_client.harvest(&farmer_1, &0), estimate_1.reward);
+
+    // Until the ring wraps around onto its slot
+    expire_block_summary(&harness, 0);
+
+    assert_eq!(farm_client.block_summary(&0), None);
+
+    let err = farm_client.try_harvest(&farmer_2, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::BlockMissing.into());
+}
+
+#[test]
+fn test_block_summary_window() {
+    // A block a day keeps a week of summaries in 7 slots rather than KALE's `BLOCK_SUMMARIES`
+    let harness = FarmHarness::with_config(60 * 60 * 24, BLOCK_REWARD, AssetMode::Mint);
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    // Only the clock moves, a day of ledgers would let the temporary blocks expire
+    for _ in 0..8 {
+        harness.plant(&harness.farmer(0), 0);
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + harness.interval);
+    }
+
+    harness.plant(&harness.farmer(0), 0);
+
+    assert_eq!(harness.index(), 8);
+    assert_eq!(farm_client.block_summary(&0), None);
+    assert_eq!(farm_client.block_summary(&7).unwrap().index, 7);
+    assert_eq!(farm_client.block_summary(&1).unwrap().index, 1);
+
+    // Old enough to be abandoned after a week of blocks too
+    assert_eq!(farm_client.prune_blocks(&0, &3), 2);
+    assert_eq!(harness.block(1), None);
+    assert!(harness.block(2).is_some());
+}
+
+#[test]
+fn test_harvest_and_plant() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(0);
+    let farmer_3 = harness.farmer(0);
+
+    for farmer in [&farmer_1, &farmer_2, &farmer_3] {
+        harness.plant(farmer, harness.balance(farmer));
+        harness.work(farmer, 4);
+    }
+
+    let estimate_1 = farm_client.estimate_reward(&farmer_1, &0);
+    let estimate_2 = farm_client.estimate_reward(&farmer_2, &0);
+
+    harness.next_block();
+
+    let err = farm_client
+        .try_harvest_and_plant(&farmer_1, &0, &10_001)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::RestakeInvalid.into());
+
+    // The first farmer into the new block closes the one it harvests
+    let reward = farm_client.harvest_and_plant(&farmer_1, &0, &2_500);
+
+    // Only the unstaked portion is minted, nothing is burned back
+    let asset_events: std::vec::Vec<_> = env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| contract == &harness.asset)
+        .map(|(_, topics, _)| Symbol::try_from_val(env, &topics.get(0).unwrap()).unwrap())
+        .collect();
+
+    assert_eq!(asset_events, [Symbol::new(env, "mint")]);
+
+    let restake = reward / 4;
+
+    assert_eq!(reward, estimate_1.reward);
+    assert_eq!(harness.index(), 1);
+    assert_eq!(harness.balance(&farmer_1), reward - restake);
+    assert_eq!(harness.pail(&farmer_1, 1).unwrap().stake, restake);
+    assert_eq!(harness.block(1).unwrap().staked_total, restake);
+
+    // Nothing is harvested if the planting half fails
+    harness.plant(&farmer_2, 0);
+
+    let err = farm_client
+        .try_harvest_and_plant(&farmer_2, &0, &0)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::PailExists.into());
+    assert_eq!(farm_client.harvest(&farmer_2, &0), estimate_2.reward);
+
+    // Restaking everything mints nothing at all
+    let reward_3 = farm_client.harvest_and_plant(&farmer_3, &0, &10_000);
+
+    assert_eq!(harness.balance(&farmer_3), 0);
+    assert_eq!(harness.pail(&farmer_3, 1).unwrap().stake, reward_3);
+}
+
+#[test]
+fn test_vesting() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    for schedule in [
+        VestingSchedule {
+            bps: 10_001,
+            ledgers: 1_000,
+        },
+        VestingSchedule {
+            bps: 5_000,
+            ledgers: 0,
+        },
+    ] {
+        let err = farm_client.try_set_vesting(&schedule).unwrap_err().unwrap();
+
+        assert_eq!(err, Errors::VestingInvalid.into());
+    }
+
+    farm_client.set_vesting(&VestingSchedule {
+        bps: 5_000,
+        ledgers: 1_000,
+    });
+
+    let farmer = harness.farmer(1_0000000);
+
+    harness.plant(&farmer, 1_0000000);
+    harness.work(&farmer, 4);
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    let reward = harness.harvest(&farmer, 0);
+    let locked = (reward - 1_0000000) / 2;
+    let sequence = env.ledger().sequence();
+
+    // The returned stake and the other half of the earnings are liquid right away
+    assert_eq!(harness.balance(&farmer), reward - locked);
+    assert_eq!(
+        farm_client.vesting(&farmer),
+        Some(Vesting {
+            unlocked: 0,
+            locked,
+            start: sequence,
+            end: sequence + 1_000,
+        })
+    );
+
+    harness.advance_ledgers(250);
+
+    let vesting = farm_client.vesting(&farmer).unwrap();
+
+    assert_eq!(vesting.unlocked, locked / 4);
+    assert_eq!(vesting.locked, locked - locked / 4);
+    assert_eq!(farm_client.claim_vested(&farmer), locked / 4);
+    assert_eq!(harness.balance(&farmer), reward - locked + locked / 4);
+
+    harness.advance_ledgers(1_000);
+
+    assert_eq!(farm_client.claim_vested(&farmer), locked - locked / 4);
+    assert_eq!(harness.balance(&farmer), reward);
+    assert_eq!(farm_client.vesting(&farmer), None);
+
+    let err = farm_client.try_claim_vested(&farmer).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::VestingMissing.into());
+
+    // A second harvest halfway through merges into what's still locked instead of restarting it
+    harness.plant(&farmer, 0);
+    harness.work(&farmer, 4);
+    harness.next_block();
+    harness.plant(&farmer, 0);
+
+    let locked = harness.harvest(&farmer, 1) / 2;
+    let sequence = env.ledger().sequence();
+
+    assert_eq!(farm_client.vesting(&farmer).unwrap().end, sequence + 1_000);
+
+    harness.work(&farmer, 4);
+    harness.next_block();
+    harness.advance_ledgers(500 - BLOCK_INTERVAL as u32 / 5);
+    harness.plant(&harness.farmer(0), 0);
+
+    let sequence = env.ledger().sequence();
+    let unlocked = locked / 2;
+    let remaining = locked - unlocked;
+
+    assert_eq!(harness.harvest(&farmer, 2) / 2, locked);
+    assert_eq!(
+        farm_client.vesting(&farmer),
+        Some(Vesting {
+            unlocked,
+            locked: remaining + locked,
+            start: sequence,
+            // weighted by amount between the 500 ledgers left and the new 1000
+            end: sequence + ((remaining * 500 + locked * 1_000) / (remaining + locked)) as u32,
+        })
+    );
+    assert!(farm_client.vesting(&farmer).unwrap().end < sequence + 1_000);
+}
+
+#[test]
+fn test_block_config() {
+    // A farm minting 1 unit every minute instead of KALE's 5 every 5 minutes
+    let harness = FarmHarness::with_config(60, 1_0000000, AssetMode::Mint);
+    let env = &harness.env;
+
+    let farmer = harness.farmer(1_0000000);
+
+    harness.plant(&farmer, 1_0000000);
+    harness.work(&farmer, 4);
+
+    // Still inside the minute, the block stays open
+    env.ledger().set_timestamp(env.ledger().timestamp() + 59);
+    harness.plant(&harness.farmer(0), 0);
+
+    assert_eq!(harness.index(), 0);
+
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    assert_eq!(harness.index(), 1);
+
+    let summary = harness.farm_client.block_summary(&0).unwrap();
+
+    assert_eq!(summary.reward_pool, 1_0000000);
+    assert_eq!(harness.harvest(&farmer, 0), 2_0000000);
+}
+
+// A farm upgraded mid-block, with its open block, farm block and a pail written by the contract before blocks grew
+#[test]
+fn test_legacy_block() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(0);
+    let farmer_2 = harness.farmer(0);
+
+    let block = Block {
+        timestamp: env.ledger().timestamp(),
+        min_gap: 0,
+        min_stake: 0,
+        min_zeros: 0,
+        max_gap: 0,
+        max_stake: 0,
+        max_zeros: 0,
+        entropy: BytesN::from_array(env, &[0; 32]),
+        staked_total: 1_0000000,
+        normalized_total: 0,
+        algorithm: HashAlgorithm::Keccak256,
+        difficulty: Difficulty::Nibbles,
+        normalization: Normalization::default(),
+        pails: u32::MAX,
+        fee: None,
+    };
+    let farm_block = Block {
+        min_gap: u32::MAX,
+        min_stake: 1_0000000,
+        min_zeros: u32::MAX,
+        max_stake: 1_0000000,
+        ..block.clone()
+    };
+
+    harness.set_legacy_block(&Storage::Block(0), &block);
+    harness.set_legacy_block(&Storage::FarmBlock, &farm_block);
+    harness.set(
+        &Storage::Pail(farmer_1.clone(), 0),
+        &Pail {
+            sequence: env.ledger().sequence(),
+            gap: None,
+            stake: 1_0000000,
+            zeros: None,
+        },
+    );
+
+    // Opened before the algorithm, difficulty and normalization were per block, and without a pail count
+    assert_eq!(harness.block(0), Some(block));
+
+    harness.plant(&farmer_2, 0);
+    harness.advance_ledgers(1);
+
+    let (nonce, hash) = harness.mine(&farmer_1, 8);
+
+    farm_client.work(&farmer_1, &hash, &nonce);
+
+    // Still counted in nibbles, the scale the block was opened with
+    assert_eq!(
+        harness.pail(&farmer_1, 0).unwrap().zeros,
+        Some(kale_core::count_zeros(
+            &hash.to_array(),
+            kale_core::Difficulty::Nibbles
+        ))
+    );
+    assert_eq!(harness.block(0).unwrap().pails, u32::MAX);
+
+    harness.env.as_contract(&harness.farm, || {
+        assert!(!env.storage().instance().has(&Storage::FarmBlock));
+        assert!(env.storage().temporary().has(&Storage::BlockV2(0)));
+    });
+
+    harness.next_block();
+    harness.plant(&farmer_2, 0);
+
+    assert_eq!(harness.block(1).unwrap().difficulty, Difficulty::Bits);
+    assert_eq!(harness.harvest(&farmer_1, 0), 1_0000000 + BLOCK_REWARD);
+
+    // Its uncounted pails keep it around until it's old enough to be abandoned
+    let err = farm_client.try_remove_block(&0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::BlockNotSettled.into());
+}
+
+#[test]
+fn test_escrow() {
+    let harness = FarmHarness::with_config(BLOCK_INTERVAL, BLOCK_REWARD, AssetMode::Escrow);
+    let farm_client = &harness.farm_client;
+
+    // The farm never becomes admin, it only ever moves balances around
+    assert_eq!(
+        token::StellarAssetClient::new(&harness.env, &harness.asset).admin(),
+        harness.homesteader
+    );
+
+    harness.mint(&harness.farm, BLOCK_REWARD);
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(5_0000000);
+
+    harness.plant(&farmer_1, 1_0000000);
+    harness.plant(&farmer_2, 5_0000000);
+    harness.work(&farmer_1, 4);
+
+    // Stakes are held in escrow, not added to the treasury
+    assert_eq!(harness.balance(&harness.farm), BLOCK_REWARD + 6_0000000);
+    assert_eq!(farm_client.treasury(), BLOCK_REWARD);
+
+    harness.next_block();
+
+    let farmer_3 = harness.farmer(0);
+
+    harness.plant(&farmer_3, 0);
+    harness.work(&farmer_3, 4);
+
+    // Closing the block releases all its stakes into the treasury, which pays back the worked ones
+    assert_eq!(farm_client.treasury(), BLOCK_REWARD + 6_0000000);
+
+    let reward_1 = harness.harvest(&farmer_1, 0);
+
+    assert_eq!(reward_1, 1_0000000 + BLOCK_REWARD + 5_0000000);
+    assert_eq!(harness.balance(&farmer_1), reward_1);
+    assert_eq!(farm_client.treasury(), 0);
+
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    // An empty treasury still harvests, the reward is owed until it's topped up
+    assert_eq!(harness.harvest(&farmer_3, 1), BLOCK_REWARD);
+    assert_eq!(harness.balance(&farmer_3), 0);
+    assert_eq!(
+        farm_client.vesting(&farmer_3).unwrap().unlocked,
+        BLOCK_REWARD
+    );
+
+    harness.mint(&harness.farm, BLOCK_REWARD / 2);
+
+    assert_eq!(farm_client.claim_vested(&farmer_3), BLOCK_REWARD / 2);
+    assert_eq!(farm_client.claim_vested(&farmer_3), 0);
+
+    harness.mint(&harness.farm, BLOCK_REWARD / 2);
+
+    assert_eq!(farm_client.claim_vested(&farmer_3), BLOCK_REWARD / 2);
+    assert_eq!(harness.balance(&farmer_3), BLOCK_REWARD);
+    assert_eq!(farm_client.vesting(&farmer_3), None);
+    assert_eq!(farm_client.treasury(), 0);
+}
+
+#[test]
+fn test_escrow_release() {
+    let harness = FarmHarness::with_config(BLOCK_INTERVAL, BLOCK_REWARD, AssetMode::Escrow);
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(2_0000000);
+
+    harness.plant(&farmer_1, 1_0000000);
+    harness.work(&farmer_1, 4);
+    harness.next_block();
+    harness.plant(&farmer_2, 2_0000000);
+
+    // A worked pail that's never harvested doesn't hold its stake out of the treasury
+    assert_eq!(farm_client.treasury(), 1_0000000);
+
+    evict_pail(&harness, &farmer_1, 0);
+
+    assert_eq!(farm_client.treasury(), 1_0000000);
+
+    // Neither does a block that's evicted before it closes
+    harness.work(&farmer_2, 4);
+    evict_block(&harness, 1);
+    harness.next_block();
+    harness.plant(&harness.farmer(0), 0);
+
+    assert_eq!(harness.index(), 2);
+    assert_eq!(farm_client.treasury(), 3_0000000);
+    assert_eq!(harness.balance(&harness.farm), 3_0000000);
+}
+
+#[test]
+fn test_fee() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let recipient = Address::generate(env);
+
+    let err = farm_client
+        .try_set_fee(&FeeSchedule {
+            recipient: recipient.clone(),
+            bps: 10_001,
+        })
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::FeeInvalid.into());
+
+    farm_client.set_fee(&FeeSchedule {
+        recipient: recipient.clone(),
+        bps: 1_000,
+    });
+
+    let fee = BLOCK_REWARD / 10;
+    let farmer = harness.farmer(1_0000000);
+
+    harness.plant(&farmer, 1_0000000);
+    harness.work(&farmer, 4);
+    harness.next_block();
+
+    harness.plant(&farmer, 0);
+
+    let fee_events: std::vec::Vec<_> = env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| contract == &harness.farm)
+        .map(|(_, topics, data)| {
+            (
+                Symbol::try_from_val(env, &topics.get(0).unwrap()).unwrap(),
+                Address::try_from_val(env, &topics.get(1).unwrap()).unwrap(),
+                <(u32, i128)>::try_from_val(env, &data).unwrap(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        fee_events,
+        [(Symbol::new(env, "fee"), recipient.clone(), (0, fee))]
+    );
+
+    // The fee comes out of the block reward when the block closes, the stake is untouched
+    assert_eq!(harness.balance(&recipient), fee);
+    assert_eq!(farm_client.fees(), fee);
+    assert_eq!(
+        farm_client.block_summary(&0).unwrap().reward_pool,
+        BLOCK_REWARD - fee
+    );
+    assert_eq!(harness.harvest(&farmer, 0), 1_0000000 + BLOCK_REWARD - fee);
+
+    harness.work(&farmer, 4);
+    harness.next_block();
+    harness.plant(&farmer, 0);
+
+    assert_eq!(harness.balance(&recipient), fee * 2);
+    assert_eq!(farm_client.fees(), fee * 2);
+
+    // Raising the fee after a block closed doesn't reach back into it
+    farm_client.set_fee(&FeeSchedule {
+        recipient: recipient.clone(),
+        bps: 5_000,
+    });
+
+    assert_eq!(
+        farm_client.estimate_reward(&farmer, &1).reward,
+        BLOCK_REWARD - fee
+    );
+
+    // Not even once the summary is gone and the block entry is summarized again
+    expire_block_summary(&harness, 1);
+
+    assert_eq!(harness.harvest(&farmer, 1), BLOCK_REWARD - fee);
+    assert_eq!(harness.balance(&recipient), fee * 2);
+}
+
+#[test]
+fn test_supply_stats() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let recipient = Address::generate(&harness.env);
+
+    farm_client.set_fee(&FeeSchedule {
+        recipient: recipient.clone(),
+        bps: 1_000,
+    });
+    farm_client.set_vesting(&VestingSchedule {
+        bps: 5_000,
+        ledgers: 100,
+    });
+
+    let farmer_1 = harness.farmer(3_0000000);
+    let farmer_2 = harness.farmer(2_0000000);
+    let farmer_3 = harness.farmer(1_0000000);
+    let holders = [&farmer_1, &farmer_2, &farmer_3, &recipient];
+    let supply = |holders: &[&Address]| -> i128 {
+        holders.iter().map(|holder| harness.balance(holder)).sum()
+    };
+    let initial = supply(&holders);
+
+    for farmer in [&farmer_1, &farmer_2, &farmer_3] {
+        harness.plant(farmer, harness.balance(farmer));
+    }
+
+    // farmer_3 never works and forfeits its stake
+    harness.work(&farmer_1, 4);
+    harness.work(&farmer_2, 6);
+    harness.next_block();
+
+    farm_client.harvest_and_plant(&farmer_1, &0, &5_000);
+    harness.plant(&farmer_2, 0);
+    harness.harvest(&farmer_2, 0);
+    harness.work(&farmer_1, 4);
+    harness.work(&farmer_2, 4);
+    harness.next_block();
+    harness.plant(&farmer_3, 0);
+    harness.harvest(&farmer_1, 1);
+    harness.advance_ledgers(100);
+    farm_client.claim_vested(&farmer_1);
+    farm_client.claim_vested(&farmer_2);
+
+    let stats = farm_client.supply_stats();
+
+    assert_eq!(stats.burned, 6_0000000);
+    assert_eq!(stats.forfeited, 1_0000000);
+    assert_eq!(stats.fees, BLOCK_REWARD / 10 * 2);
+    assert_eq!(stats.fees, farm_client.fees());
+    assert_eq!(harness.balance(&recipient), stats.fees);
+
+    // Every balance change the farm caused is accounted for
+    assert_eq!(
+        supply(&holders),
+        initial + stats.minted_stake + stats.minted_reward + stats.fees - stats.burned
+    );
+
+    // Farmers never get more than their stakes and the two blocks' rewards after fees
+    assert!(
+        stats.minted_stake + stats.minted_reward
+            <= stats.burned + 2 * (BLOCK_REWARD - BLOCK_REWARD / 10)
+    );
+}
+
+#[test]
+fn test_blocklist() {
+    let harness = FarmHarness::new();
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    let blocked = harness.farmer(1_0000000);
+    let farmer = harness.farmer(1_0000000);
+
+    harness.plant(&blocked, 1_0000000);
+    harness.plant(&farmer, 1_0000000);
+    harness.work(&blocked, 4);
+    harness.work(&farmer, 4);
+
+    farm_client.block_farmer(&blocked, &true);
+
+    let block_events: std::vec::Vec<_> = env
+        .events()
+        .all()
+        .iter()
+        .filter(|(contract, _, _)| contract == &harness.farm)
+        .map(|(_, topics, _)| Symbol::try_from_val(env, &topics.get(0).unwrap()).unwrap())
+        .collect();
+
+    assert_eq!(
+        block_events,
+        [Symbol::new(env, "forfeit"), Symbol::new(env, "blocked")]
+    );
+
+    assert!(farm_client.blocked(&blocked));
+    assert_eq!(harness.pail(&blocked, 0), None);
+
+    let err = farm_client.try_plant(&blocked, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmerBlocked.into());
+
+    let err = farm_client
+        .try_work(&blocked, &BytesN::from_array(env, &[0; 32]), &0)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::FarmerBlocked.into());
+
+    harness.next_block();
+    harness.plant(&farmer, 0);
+
+    // The forfeited stake and share went to the block's only other farmer
+    assert_eq!(
+        farm_client.block_summary(&0).unwrap().reward_pool,
+        BLOCK_REWARD + 1_0000000
+    );
+    assert_eq!(
+        harness.harvest(&farmer, 0),
+        1_0000000 + BLOCK_REWARD + 1_0000000
+    );
+
+    // Without forfeiting the pail stays put but can't be harvested
+    harness.work(&farmer, 4);
+    farm_client.block_farmer(&farmer, &false);
+    harness.next_block();
+
+    farm_client.unblock_farmer(&blocked);
+    harness.plant(&blocked, 0);
+
+    let err = farm_client.try_harvest(&farmer, &1).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmerBlocked.into());
+
+    // Nor moved into the greenhouse
+    let err = farm_client
+        .try_greenhouse(&farmer, &1)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::FarmerBlocked.into());
+
+    farm_client.unblock_farmer(&farmer);
+
+    assert!(!farm_client.blocked(&farmer));
+    assert_eq!(harness.harvest(&farmer, 1), BLOCK_REWARD);
+}
+
+#[test]
+fn test_remove_block() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(1_0000000);
+
+    harness.plant(&farmer_1, 1_0000000);
+    harness.plant(&farmer_2, 0);
+    harness.work(&farmer_1, 4);
+    harness.work(&farmer_2, 4);
+
+    assert_eq!(harness.block(0).unwrap().pails, 2);
+
+    // The open block is never removable
+    let err = farm_client.try_remove_block(&0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::BlockNotSettled.into());
+
+    harness.next_block();
+    harness.plant(&farmer_1, 0);
+
+    // Closed but with pails still to harvest
+    let err = farm_client.try_remove_block(&0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::BlockNotSettled.into());
+
+    harness.harvest(&farmer_1, 0);
+    farm_client.greenhouse(&farmer_2, &0);
+
+    assert_eq!(harness.block(0).unwrap().pails, 0);
+
+    farm_client.remove_block(&0);
+
+    assert_eq!(harness.block(0), None);
+
+    let err = farm_client.try_remove_block(&0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::BlockMissing.into());
+
+    // Block 1 holds an unworked pail that can never be harvested, so it only goes once it's old enough
+    harness.next_block();
+    harness.plant(&farmer_2, 0);
+
+    assert_eq!(farm_client.prune_blocks(&0, &3), 0);
+    assert!(harness.block(1).is_some());
+
+    harness.set(&Storage::FarmIndex, &(1 + BLOCK_SUMMARIES));
+
+    // Block 2 is closed now too but isn't as old and still has its pail
+    assert_eq!(farm_client.prune_blocks(&0, &3), 1);
+    assert_eq!(harness.block(1), None);
+    assert!(harness.block(2).is_some());
+}
+
+#[test]
+fn test_refund() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(2_0000000);
+
+    harness.plant(&farmer_1, 1_0000000);
+    harness.plant(&farmer_2, 2_0000000);
+    harness.work(&farmer_1, 4);
+
+    let err = farm_client.try_refund(&farmer_1, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::RefundNotAllowed.into());
+
+    farm_client.enter_emergency(&vec![&harness.env, 0]);
+
+    assert_eq!(farm_client.refunds(), vec![&harness.env, 0]);
+
+    let err = farm_client.try_plant(&farmer_1, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmPaused.into());
+
+    // Worked or not, the stake comes back and the pail is gone
+    assert_eq!(farm_client.refund(&farmer_1, &0), 1_0000000);
+    assert_eq!(farm_client.refund(&farmer_2, &0), 2_0000000);
+    assert_eq!(harness.balance(&farmer_1), 1_0000000);
+    assert_eq!(harness.balance(&farmer_2), 2_0000000);
+    assert_eq!(harness.pail(&farmer_1, 0), None);
+
+    let block = harness.block(0).unwrap();
+
+    assert_eq!(
+        (block.staked_total, block.normalized_total, block.pails),
+        (0, 0, 0)
+    );
+
+    let supply = farm_client.supply_stats();
+
+    assert_eq!((supply.burned, supply.minted_stake), (3_0000000, 3_0000000));
+
+    let err = farm_client.try_refund(&farmer_1, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::PailMissing.into());
+
+    let err = farm_client.try_refund(&farmer_1, &1).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::RefundNotAllowed.into());
+
+    farm_client.exit_emergency();
+
+    assert_eq!(farm_client.refunds(), vec![&harness.env]);
+
+    // Back to farming once unpaused
+    farm_client.unpause();
+
+    harness.plant(&farmer_1, 1_0000000);
+    harness.work(&farmer_1, 4);
+    harness.next_block();
+    harness.plant(&farmer_2, 0);
+
+    // A closed block's stakes are already part of its reward pool, its pails get harvested instead
+    let err = farm_client
+        .try_enter_emergency(&vec![&harness.env, 0, 1])
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::RefundNotAllowed.into());
+    assert_eq!(farm_client.refunds(), vec![&harness.env]);
+
+    farm_client.enter_emergency(&vec![&harness.env, 1]);
+
+    let err = farm_client.try_refund(&farmer_1, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::RefundNotAllowed.into());
+    assert_eq!(harness.harvest(&farmer_1, 0), 1_0000000 + BLOCK_REWARD);
+}
+
+#[test]
+fn test_pause() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer = harness.farmer(1_0000000);
+
+    let err = farm_client.try_unpause().unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmNotPaused.into());
+
+    farm_client.pause();
+
+    let err = farm_client.try_pause().unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmPaused.into());
+
+    let err = farm_client.try_plant(&farmer, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmPaused.into());
+
+    // Kept with the instance, whose TTL every call extends, rather than in an entry that could be archived
+    harness.env.as_contract(&harness.farm, || {
+        assert!(harness.env.storage().instance().has(&Storage::FarmPaused));
+        assert!(!harness.env.storage().persistent().has(&Storage::FarmPaused));
+    });
+
+    farm_client.unpause();
+
+    harness.plant(&farmer, 1_0000000);
+
+    assert_eq!(harness.pail(&farmer, 0).unwrap().stake, 1_0000000);
+}
+
+#[test]
+fn test_sunset() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer = harness.farmer(1_0000000);
+    let new_admin = Address::generate(&harness.env);
+
+    harness.plant(&farmer, 1_0000000);
+    harness.work(&farmer, 4);
+
+    let err = farm_client
+        .try_complete_sunset(&new_admin)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SunsetNotReady.into());
+
+    farm_client.begin_sunset(&1);
+
+    // Blocks up to the final one still open, get worked and harvested
+    harness.next_block();
+    harness.plant(&farmer, 0);
+    harness.work(&farmer, 4);
+
+    assert_eq!(harness.harvest(&farmer, 0), 1_0000000 + BLOCK_REWARD);
+
+    let err = farm_client.try_begin_sunset(&0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::SunsetInvalid.into());
+
+    harness.next_block();
+
+    let err = farm_client.try_plant(&farmer, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmSunset.into());
+
+    // Harvesting the final block closes it, which starts the window
+    assert_eq!(harness.harvest(&farmer, 1), BLOCK_REWARD);
+    assert!(farm_client.sunset().unwrap().closed.is_some());
+
+    let err = farm_client
+        .try_complete_sunset(&new_admin)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SunsetNotReady.into());
+
+    let err = farm_client.try_begin_sunset(&5).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::SunsetInvalid.into());
+
+    harness.advance_ledgers(SUNSET_WINDOW);
+    farm_client.complete_sunset(&new_admin);
+
+    assert_eq!(
+        token::StellarAssetClient::new(&harness.env, &harness.asset).admin(),
+        new_admin
+    );
+    assert!(farm_client.sunset().unwrap().complete);
+
+    // Locked for good
+    let err = farm_client.try_plant(&farmer, &0).unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmLocked.into());
+
+    let err = farm_client.try_unpause().unwrap_err().unwrap();
+
+    assert_eq!(err, Errors::FarmLocked.into());
+}
+
+#[test]
+fn test_sunset_claims() {
+    // Each of the outstanding claims holds the sunset up on its own, whichever is settled last
+    for last in 0..3 {
+        let harness = FarmHarness::new();
+        let farm_client = &harness.farm_client;
+
+        let farmer_1 = harness.farmer(1_0000000);
+        let farmer_2 = harness.farmer(0);
+        let new_admin = Address::generate(&harness.env);
+
+        // Vesting longer than the sunset's harvest window
+        farm_client.set_vesting(&VestingSchedule {
+            bps: 5_000,
+            ledgers: SUNSET_WINDOW * 2,
+        });
+        farm_client.create_season(&0, &0, &100_0000000, &1);
+        farm_client.begin_sunset(&0);
+
+        harness.plant(&farmer_1, 1_0000000);
+        harness.plant(&farmer_2, 0);
+        harness.work(&farmer_1, 4);
+        harness.work(&farmer_2, 4);
+        harness.next_block();
+
+        harness.harvest(&farmer_1, 0);
+        farm_client.greenhouse(&farmer_2, &0);
+
+        // Later payouts don't vest, so settling one claim never reopens another
+        farm_client.set_vesting(&VestingSchedule { bps: 0, ledgers: 0 });
+
+        harness.advance_ledgers(SUNSET_WINDOW);
+
+        let settle = |claim: u32| match claim {
+            0 => {
+                harness.advance_ledgers(SUNSET_WINDOW);
+                assert!(farm_client.claim_vested(&farmer_1) > 0);
+            }
+            1 => {
+                harness.harvest(&farmer_2, 0);
+            }
+            _ => {
+                assert_eq!(farm_client.claim_season_bonus(&farmer_1, &0), 100_0000000);
+            }
+        };
+
+        for claim in (0..3).filter(|claim| *claim != last) {
+            settle(claim);
+        }
+
+        let err = farm_client
+            .try_complete_sunset(&new_admin)
+            .unwrap_err()
+            .unwrap();
+
+        assert_eq!(err, Errors::ClaimsOutstanding.into());
+
+        settle(last);
+        farm_client.complete_sunset(&new_admin);
+
+        assert!(farm_client.vesting(&farmer_1).is_none());
+        assert!(farm_client.sunset().unwrap().complete);
+    }
+}
+
+#[test]
+fn test_forfeit_claims() {
+    let harness = FarmHarness::new();
+    let farm_client = &harness.farm_client;
+
+    let farmer_1 = harness.farmer(1_0000000);
+    let farmer_2 = harness.farmer(0);
+    let new_admin = Address::generate(&harness.env);
+
+    farm_client.set_vesting(&VestingSchedule {
+        bps: 5_000,
+        ledgers: SUNSET_WINDOW,
+    });
+    farm_client.create_season(&0, &0, &100_0000000, &1);
+    farm_client.begin_sunset(&0);
+
+    harness.plant(&farmer_1, 1_0000000);
+    harness.plant(&farmer_2, 0);
+    harness.work(&farmer_1, 4);
+    harness.work(&farmer_2, 4);
+    harness.next_block();
+
+    harness.harvest(&farmer_1, 0);
+    farm_client.greenhouse(&farmer_2, &0);
+
+    assert_eq!(harness.get::<u32>(&Storage::FarmUnclaimed), Some(1));
+
+    // Only blocked farmers' claims can be forfeited
+    let err = farm_client
+        .try_forfeit_claims(&farmer_1, &vec![&harness.env], &vec![&harness.env, 0])
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::FarmerNotBlocked.into());
+
+    farm_client.block_farmer(&farmer_1, &false);
+    farm_client.block_farmer(&farmer_2, &false);
+    harness.advance_ledgers(SUNSET_WINDOW);
+
+    // Blocked farmers can't settle their claims, which would otherwise hold the sunset up for good
+    let err = farm_client
+        .try_claim_vested(&farmer_1)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::FarmerBlocked.into());
+
+    let err = farm_client
+        .try_complete_sunset(&new_admin)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::ClaimsOutstanding.into());
+
+    farm_client.forfeit_claims(&farmer_1, &vec![&harness.env], &vec![&harness.env, 0]);
+
+    assert!(farm_client.vesting(&farmer_1).is_none());
+    assert!(
+        farm_client
+            .season(&0)
+            .unwrap()
+            .leaderboard
+            .get_unchecked(0)
+            .claimed
+    );
+    assert_eq!(harness.get::<u32>(&Storage::FarmUnclaimed), Some(0));
+
+    let err = farm_client
+        .try_complete_sunset(&new_admin)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::ClaimsOutstanding.into());
+
+    farm_client.forfeit_claims(&farmer_2, &vec![&harness.env, 0], &vec![&harness.env]);
+
+    assert!(harness
+        .get::<Pail>(&Storage::GreenhousePail(farmer_2.clone(), 0))
+        .is_none());
+    assert!(harness.get::<Greenhouse>(&Storage::Greenhouse(0)).is_none());
+
+    farm_client.complete_sunset(&new_admin);
+
+    assert!(farm_client.sunset().unwrap().complete);
+}
+
+#[test]
+fn test_season() {
+    // A block a day keeps 7 summaries, so seasons settle a week of blocks after they end
+    let harness = FarmHarness::with_config(60 * 60 * 24, BLOCK_REWARD, AssetMode::Mint);
+    let env = &harness.env;
+    let farm_client = &harness.farm_client;
+
+    // Only the clock moves far, a day of ledgers would let the temporary blocks expire
+    let next_block = || {
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + harness.interval);
+        harness.advance_ledgers(1);
+    };
+
+    let bonus = 100_0000000;
+
+    for (start, end, bonus, leaders) in [
+        (1, 0, bonus, 2),
+        (0, 1, -1, 2),
+        (0, 1, bonus, 0),
+        (0, 1, bonus, SEASON_LEADERS + 1),
+    ] {
+        let err = farm_client
+            .try_create_season(&start, &end, &bonus, &leaders)
+            .unwrap_err()
+            .unwrap();
+
+        assert_eq!(err, Errors::SeasonInvalid.into());
+    }
+
+    assert_eq!(farm_client.create_season(&0, &1, &bonus, &2), 0);
+
+    // Seasons don't overlap
+    let err = farm_client
+        .try_create_season(&1, &2, &bonus, &2)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SeasonInvalid.into());
+
+    let farmer_1 = harness.farmer(4_0000000);
+    let farmer_2 = harness.farmer(2_0000000);
+    let farmer_3 = harness.farmer(0);
+
+    harness.plant(&harness.farmer(0), 0);
+
+    // The next season can be lined up while this one runs without cutting it short
+    assert_eq!(farm_client.create_season(&3, &4, &bonus, &1), 1);
+    assert_eq!(farm_client.season_count(), 2);
+
+    for index in 0..4 {
+        harness.plant(&farmer_1, 1_0000000);
+        harness.plant(&farmer_2, 5000000);
+        harness.plant(&farmer_3, 0);
+
+        harness.advance_ledgers(1);
+
+        for farmer in [&farmer_1, &farmer_2, &farmer_3] {
+            harness.work(farmer, 4);
+        }
+
+        assert_eq!(harness.index(), index);
+
+        next_block();
+    }
+
+    harness.plant(&farmer_3, 0);
+
+    // Nothing counts until it's harvested
+    assert_eq!(farm_client.season_contribution(&farmer_1, &0), 0);
+    assert_eq!(farm_client.season(&0).unwrap().leaderboard.len(), 0);
+
+    let mut earnings = [0, 0];
+
+    for index in 0..2 {
+        earnings[0] += harness.harvest(&farmer_1, index) - 1_0000000;
+        earnings[1] += harness.harvest(&farmer_2, index) - 5000000;
+    }
+
+    harness.harvest(&farmer_3, 0);
+    farm_client.greenhouse(&farmer_3, &1);
+
+    // Contributions are the rewards earned in the season's blocks, harvesting the next season's blocks counts there
+    let contribution_1 = farm_client.season_contribution(&farmer_1, &0);
+    let contribution_2 = farm_client.season_contribution(&farmer_2, &0);
+    let contribution_3 = farm_client.season_contribution(&farmer_3, &0);
+
+    assert_eq!([contribution_1, contribution_2], earnings);
+    assert!(contribution_1 > contribution_2);
+    assert!(contribution_2 > contribution_3);
+    assert!(contribution_3 > 0);
+
+    harness.harvest(&farmer_2, 3);
+
+    assert_eq!(
+        farm_client.season_contribution(&farmer_2, &0),
+        contribution_2
+    );
+    assert!(farm_client.season_contribution(&farmer_2, &1) > 0);
+
+    // Closed blocks are harvestable from their summaries for a while, the leaderboard waits for them
+    let err = farm_client
+        .try_claim_season_bonus(&farmer_1, &0)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SeasonNotOver.into());
+
+    // Past both seasons' last block plus the summary window
+    while harness.index() <= 4 + 7 {
+        next_block();
+        harness.plant(&farmer_3, 0);
+    }
+
+    // Late harvests don't move a final leaderboard
+    harness.harvest(&farmer_3, 1);
+
+    assert_eq!(
+        farm_client.season_contribution(&farmer_3, &0),
+        contribution_3
+    );
+
+    let season = farm_client.season(&0).unwrap();
+
+    assert_eq!(
+        season
+            .leaderboard
+            .iter()
+            .map(|standing| (standing.farmer, standing.contribution))
+            .collect::<std::vec::Vec<_>>(),
+        [
+            (farmer_1.clone(), contribution_1),
+            (farmer_2.clone(), contribution_2)
+        ]
+    );
+
+    let err = farm_client
+        .try_claim_season_bonus(&farmer_3, &0)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::BonusMissing.into());
+
+    let balance_1 = harness.balance(&farmer_1);
+    let bonus_1 = farm_client.claim_season_bonus(&farmer_1, &0);
+    let bonus_2 = farm_client.claim_season_bonus(&farmer_2, &0);
+
+    assert_eq!(
+        bonus_1,
+        bonus * contribution_1 / (contribution_1 + contribution_2)
+    );
+    assert_eq!(harness.balance(&farmer_1), balance_1 + bonus_1);
+    assert!(bonus_1 + bonus_2 <= bonus && bonus_1 + bonus_2 >= bonus - 1);
+
+    let err = farm_client
+        .try_claim_season_bonus(&farmer_1, &0)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::BonusClaimed.into());
+
+    // The later season's one leader claims the whole pool
+    assert_eq!(farm_client.claim_season_bonus(&farmer_2, &1), bonus);
+
+    let err = farm_client
+        .try_claim_season_bonus(&farmer_1, &2)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SeasonMissing.into());
+
+    // The open block has already started
+    let index = harness.index();
+    let err = farm_client
+        .try_create_season(&index, &(index + 1), &bonus, &1)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SeasonInvalid.into());
+
+    // A season running to the last index leaves no room for another
+    assert_eq!(
+        farm_client.create_season(&(index + 1), &u32::MAX, &bonus, &1),
+        2
+    );
+
+    let err = farm_client
+        .try_create_season(&u32::MAX, &u32::MAX, &bonus, &1)
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(err, Errors::SeasonInvalid.into());
+}
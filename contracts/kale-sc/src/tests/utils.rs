@@ -7,10 +7,9 @@ use ed25519_dalek::Keypair;
 use soroban_fixed_point_math::SorobanFixedPoint;
 use soroban_sdk::{testutils::Address as _, xdr::ToXdr, Address, Bytes, BytesN, Env};
 use stellar_strkey::{ed25519, Strkey};
-use tiny_keccak::{Hasher, Keccak};
 
 use crate::{
-    types::{Block, Pail},
+    types::{Block, Difficulty, HashAlgorithm, Normalization, Pail},
     BLOCK_REWARD,
 };
 
@@ -29,6 +28,11 @@ fn test_zero_harvest() {
         normalized_total: 109285718,
         staked_total: 0,
         timestamp: 1733940929,
+        algorithm: HashAlgorithm::Keccak256,
+        difficulty: Difficulty::Nibbles,
+        normalization: Normalization::default(),
+        pails: 0,
+        fee: None,
     };
 
     let pail = Pail {
@@ -38,19 +42,24 @@ fn test_zero_harvest() {
         zeros: Some(8),
     };
 
-    let (normalized_gap, normalized_stake, normalized_zeros) = generate_normalizations(
-        &env,
-        &block,
+    let normalizations = kale_core::generate_normalizations(
+        &(&block).into(),
+        &(&block.normalization).into(),
         pail.gap.unwrap(),
         pail.stake,
         pail.zeros.unwrap(),
-    );
+    )
+    .unwrap();
 
-    let reward = (normalized_gap + normalized_stake + normalized_zeros).fixed_mul_floor(
-        &env,
-        &(BLOCK_REWARD + block.staked_total),
-        &block.normalized_total.max(1),
-    );
+    println!("{:?}", normalizations);
+
+    let reward = kale_core::generate_reward(
+        normalizations,
+        BLOCK_REWARD + block.staked_total,
+        block.normalized_total,
+        pail.stake,
+    )
+    .unwrap();
 
     println!("{:?}", reward);
 }
@@ -181,12 +190,18 @@ fn test_unsafe_stuff() {
     let hash1: &[u8] =
         &hex::decode("00100000000f2ea462db5f7a04090430384433845c3367d55c06f20efd6656").unwrap();
 
-    println!("{:?}", check_zeros(0, hash1));
+    println!(
+        "{:?}",
+        kale_core::count_zeros(hash1, kale_core::Difficulty::Nibbles)
+    );
 
     let hash2: &[u8] =
         &hex::decode("00000000000f2ea462db5f7a04090430384433845c3367d55c06f20efd6656").unwrap();
 
-    println!("{:?}", check_zeros(2, hash2));
+    println!(
+        "{:?}",
+        kale_core::count_zeros(hash2, kale_core::Difficulty::Nibbles)
+    );
 
     // unsafe {
     //     let mut index = 0;
@@ -211,75 +226,6 @@ fn test_unsafe_stuff() {
     // }
 }
 
-pub fn find_nonce_and_hash(
-    env: &Env,
-    index: &u32,
-    entropy: &BytesN<32>,
-    farmer: &Address,
-    zeros: u32,
-) -> (u64, BytesN<32>) {
-    let mut nonce = 0;
-    let mut hash_array = generate_hash(env, index, &nonce, entropy, farmer);
-
-    // println!("{:?}", hash_array);
-
-    loop {
-        let hash = generate_keccak(&mut hash_array, &nonce);
-        let mut leading_zeros = 0;
-
-        for byte in hash {
-            if byte == 0 {
-                leading_zeros += 2;
-            } else {
-                // Use leading_zeros to count bits, convert to hex digits
-                leading_zeros += byte.leading_zeros() / 4;
-                break;
-            }
-        }
-
-        if leading_zeros >= zeros {
-            return (nonce, BytesN::from_array(env, &hash));
-        }
-
-        nonce += 1;
-    }
-}
-
-fn generate_keccak(hash_b: &mut [u8; 76], nonce: &u64) -> [u8; 32] {
-    let mut hash = [0u8; 32];
-
-    hash_b[8..16].copy_from_slice(&nonce.to_be_bytes());
-
-    let mut keccak = Keccak::v256();
-    keccak.update(hash_b);
-    keccak.finalize(&mut hash);
-
-    hash
-}
-
-fn generate_hash(
-    env: &Env,
-    index: &u32,
-    nonce: &u64,
-    entropy: &BytesN<32>,
-    farmer: &Address,
-) -> [u8; 76] {
-    let mut hash_array = [0u8; 76];
-
-    let mut farmer_array = [0u8; 32];
-    let farmer_bytes = farmer.to_xdr(env);
-    farmer_bytes
-        .slice(farmer_bytes.len() - 32..)
-        .copy_into_slice(&mut farmer_array);
-
-    hash_array[..4].copy_from_slice(&index.to_be_bytes());
-    hash_array[4..12].copy_from_slice(&nonce.to_be_bytes());
-    hash_array[12..44].copy_from_slice(&entropy.to_array());
-    hash_array[44..].copy_from_slice(&farmer_array);
-
-    hash_array
-}
-
 fn integer_nth_root(y: u64, n: u32) -> u64 {
     if y == 0 {
         return 0;
@@ -320,65 +266,3 @@ fn integer_nth_root(y: u64, n: u32) -> u64 {
 
     low - 1
 }
-
-fn check_zeros(max_zeros: u32, hash: &[u8]) -> u32 {
-    let mut zeros = max_zeros;
-    let mut index = zeros / 2;
-
-    loop {
-        let more = unsafe { hash.get_unchecked(index as usize).leading_zeros() };
-
-        if more == 8 {
-            zeros += 2;
-        } else {
-            zeros += more / 4;
-            break;
-        }
-
-        index += 1;
-    }
-
-    zeros
-}
-
-fn generate_normalizations(
-    env: &Env,
-    block: &Block,
-    gap: u32,
-    stake: i128,
-    zeros: u32,
-) -> (i128, i128, i128) {
-    // Calculate ranges
-    let range_gap = ((block.max_gap - block.min_gap) as i128).max(1);
-    let range_stake = (block.max_stake - block.min_stake).max(1);
-    let range_zeros = ((block.max_zeros - block.min_zeros) as i128).max(1);
-
-    // Find largest range for scaling
-    let max_range = range_gap.max(range_stake).max(range_zeros);
-
-    // Set minimum threshold (1% of max_range)
-    let min_threshold = (max_range / 100).max(1);
-
-    // Clamp inputs to valid ranges
-    let gap = gap.max(block.min_gap).min(block.max_gap);
-    let stake = stake.max(block.min_stake).min(block.max_stake);
-    let zeros = zeros.max(block.min_zeros).min(block.max_zeros);
-
-    // Scale each value relative to max_range
-    let normalized_gap = ((gap - block.min_gap) as i128)
-        .fixed_mul_floor(&env, &max_range, &range_gap)
-        .max(min_threshold);
-    let normalized_stake = ((stake - block.min_stake) as i128)
-        .fixed_mul_floor(&env, &max_range, &range_stake)
-        .max(min_threshold);
-    let normalized_zeros = ((zeros - block.min_zeros) as i128)
-        .fixed_mul_floor(&env, &max_range, &range_zeros)
-        .max(min_threshold);
-
-    println!(
-        "{:?} {:?} {:?}",
-        normalized_gap, normalized_stake, normalized_zeros
-    );
-
-    (normalized_gap, normalized_stake, normalized_zeros)
-}
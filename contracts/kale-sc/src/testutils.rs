@@ -0,0 +1,251 @@
+#![cfg(any(test, feature = "testutils"))]
+
+extern crate std;
+
+use soroban_sdk::{
+    map,
+    testutils::{Address as _, EnvTestConfig, Ledger},
+    token, Address, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val,
+};
+
+use crate::{
+    storage::get_block,
+    types::{AssetMode, Block, HashAlgorithm, Pail, Storage},
+    Contract, ContractClient, BLOCK_INTERVAL, BLOCK_REWARD,
+};
+
+// A farm wired to its own SAC with all auths mocked, for this crate's tests and anything built on KALE
+pub struct FarmHarness {
+    pub env: Env,
+    pub homesteader: Address,
+    pub asset: Address,
+    pub farm: Address,
+    pub farm_client: ContractClient<'static>,
+    pub asset_client: token::Client<'static>,
+    // The farm's block interval in seconds, how far `next_block` moves the clock
+    pub interval: u64,
+}
+
+impl Default for FarmHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FarmHarness {
+    pub fn new() -> Self {
+        Self::with_config(BLOCK_INTERVAL, BLOCK_REWARD, AssetMode::Mint)
+    }
+
+    // A farm constructed with its own block `interval`, `reward` and asset `mode` instead of KALE's
+    pub fn with_config(interval: u64, reward: i128, mode: AssetMode) -> Self {
+        let mut env = Env::default();
+
+        env.set_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        });
+
+        Self::setup(env, interval, reward, mode)
+    }
+
+    // Registers the SAC and farm on `env` and, unless the farm escrows, hands SAC admin over to the farm
+    pub fn setup(env: Env, interval: u64, reward: i128, mode: AssetMode) -> Self {
+        env.ledger().set_min_temp_entry_ttl(17280);
+        env.ledger().set_min_persistent_entry_ttl(2073600);
+
+        env.mock_all_auths();
+
+        let homesteader = Address::generate(&env);
+        let asset = env
+            .register_stellar_asset_contract_v2(homesteader.clone())
+            .address();
+        let farm = env.register(Contract, (&homesteader, &asset, interval, reward, mode));
+
+        if mode == AssetMode::Mint {
+            token::StellarAssetClient::new(&env, &asset).set_admin(&farm);
+        }
+
+        FarmHarness {
+            farm_client: ContractClient::new(&env, &farm),
+            asset_client: token::Client::new(&env, &asset),
+            env,
+            homesteader,
+            asset,
+            farm,
+            interval,
+        }
+    }
+
+    // A new farmer holding `amount`
+    pub fn farmer(&self, amount: i128) -> Address {
+        let farmer = Address::generate(&self.env);
+
+        self.mint(&farmer, amount);
+
+        farmer
+    }
+
+    pub fn mint(&self, to: &Address, amount: i128) {
+        if amount > 0 {
+            token::StellarAssetClient::new(&self.env, &self.asset).mint(to, &amount);
+        }
+    }
+
+    pub fn balance(&self, farmer: &Address) -> i128 {
+        self.asset_client.balance(farmer)
+    }
+
+    pub fn plant(&self, farmer: &Address, amount: i128) {
+        self.farm_client.plant(farmer, &amount);
+    }
+
+    // Finds a nonce with at least `zeros` leading zero bits for the current block
+    pub fn mine(&self, farmer: &Address, zeros: u32) -> (u64, BytesN<32>) {
+        let index = self.index();
+        let block = self
+            .block(index)
+            .expect("no open block, `plant` before mining");
+
+        find_nonce_and_hash(
+            &self.env,
+            &block.algorithm,
+            &index,
+            &block.entropy,
+            farmer,
+            zeros,
+        )
+    }
+
+    // Mines and submits `work`, returning the gap the farm recorded
+    pub fn work(&self, farmer: &Address, zeros: u32) -> u32 {
+        let (nonce, hash) = self.mine(farmer, zeros);
+
+        self.farm_client.work(farmer, &hash, &nonce)
+    }
+
+    pub fn harvest(&self, farmer: &Address, index: u32) -> i128 {
+        self.farm_client.harvest(farmer, &index)
+    }
+
+    pub fn advance_ledgers(&self, ledgers: u32) {
+        self.env
+            .ledger()
+            .set_sequence_number(self.env.ledger().sequence() + ledgers);
+    }
+
+    // Moves past the farm's interval so the next `plant` closes the current block
+    pub fn next_block(&self) {
+        self.env
+            .ledger()
+            .set_timestamp(self.env.ledger().timestamp() + self.interval);
+        self.advance_ledgers(self.interval as u32 / 5);
+    }
+
+    // Reads any farm key from whichever storage it lives in
+    pub fn get<V: TryFromVal<Env, Val>>(&self, key: &Storage) -> Option<V> {
+        let key: Val = key.into_val(&self.env);
+
+        self.env.as_contract(&self.farm, || {
+            let storage = self.env.storage();
+
+            if storage.instance().has(&key) {
+                storage.instance().get(&key)
+            } else if storage.persistent().has(&key) {
+                storage.persistent().get(&key)
+            } else {
+                storage.temporary().get(&key)
+            }
+        })
+    }
+
+    pub fn set<V: IntoVal<Env, Val>>(&self, key: &Storage, value: &V) {
+        let key: Val = key.into_val(&self.env);
+
+        self.env.as_contract(&self.farm, || {
+            let storage = self.env.storage();
+
+            if storage.instance().has(&key) {
+                storage.instance().set(&key, value);
+            } else if storage.persistent().has(&key) {
+                storage.persistent().set(&key, value);
+            } else {
+                storage.temporary().set(&key, value);
+            }
+        });
+    }
+
+    pub fn index(&self) -> u32 {
+        self.get(&Storage::FarmIndex).unwrap_or(0)
+    }
+
+    // The block as the farm sees it, migrated if it's still in the legacy layout
+    pub fn block(&self, index: u32) -> Option<Block> {
+        self.env
+            .as_contract(&self.farm, || get_block(&self.env, index))
+    }
+
+    // Writes `block` at `key` the way farms stored blocks before `BlockV2`, a map of only the original fields
+    // Built by hand rather than from `LegacyBlock` so the migration is checked against the real layout
+    pub fn set_legacy_block(&self, key: &Storage, block: &Block) {
+        let env = &self.env;
+        let legacy: Map<Symbol, Val> = map![
+            env,
+            (Symbol::new(env, "timestamp"), block.timestamp.into_val(env)),
+            (Symbol::new(env, "min_gap"), block.min_gap.into_val(env)),
+            (Symbol::new(env, "min_stake"), block.min_stake.into_val(env)),
+            (Symbol::new(env, "min_zeros"), block.min_zeros.into_val(env)),
+            (Symbol::new(env, "max_gap"), block.max_gap.into_val(env)),
+            (Symbol::new(env, "max_stake"), block.max_stake.into_val(env)),
+            (Symbol::new(env, "max_zeros"), block.max_zeros.into_val(env)),
+            (Symbol::new(env, "entropy"), block.entropy.into_val(env)),
+            (
+                Symbol::new(env, "staked_total"),
+                block.staked_total.into_val(env)
+            ),
+            (
+                Symbol::new(env, "normalized_total"),
+                block.normalized_total.into_val(env)
+            ),
+        ];
+
+        // The farm block lived in instance storage back then
+        env.as_contract(&self.farm, || match key {
+            Storage::FarmBlock => env.storage().instance().set(key, &legacy),
+            _ => env.storage().temporary().set(key, &legacy),
+        });
+    }
+
+    pub fn pail(&self, farmer: &Address, index: u32) -> Option<Pail> {
+        self.get(&Storage::Pail(farmer.clone(), index))
+    }
+}
+
+pub fn find_nonce_and_hash(
+    env: &Env,
+    algorithm: &HashAlgorithm,
+    index: &u32,
+    entropy: &BytesN<32>,
+    farmer: &Address,
+    zeros: u32,
+) -> (u64, BytesN<32>) {
+    let mut nonce = 0;
+    let mut preimage =
+        kale_core::generate_preimage(*index, nonce, &entropy.to_array(), &farmer_key(env, farmer));
+
+    loop {
+        kale_core::set_nonce(&mut preimage, nonce);
+
+        let hash = kale_core::generate_hash((*algorithm).into(), &preimage);
+
+        if kale_core::count_zeros(&hash, kale_core::Difficulty::Bits) >= zeros {
+            return (nonce, BytesN::from_array(env, &hash));
+        }
+
+        nonce += 1;
+    }
+}
+
+// The last 32 bytes of the farmer's XDR, as `work` feeds them into the preimage
+pub fn farmer_key(env: &Env, farmer: &Address) -> [u8; 32] {
+    crate::contract_farm::generate_farmer_key(env, farmer)
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address, BytesN};
+use soroban_sdk::{contracttype, Address, BytesN, Symbol, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
@@ -13,6 +13,28 @@ pub struct Block {
     pub entropy: BytesN<32>,
     pub staked_total: i128,
     pub normalized_total: i128,
+    pub algorithm: HashAlgorithm,
+    pub difficulty: Difficulty,
+    pub normalization: Normalization,
+    pub pails: u32, // planted and not yet harvested, greenhoused or forfeited, `u32::MAX` if never counted
+    pub fee: Option<i128>, // paid when the block closed
+}
+
+// The layout blocks were stored in before they carried their own algorithm, difficulty, normalization and pail count
+// Never written anymore, entries in it are still read and migrated so an upgrade doesn't strand blocks in flight
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegacyBlock {
+    pub timestamp: u64,
+    pub min_gap: u32,
+    pub min_stake: i128,
+    pub min_zeros: u32,
+    pub max_gap: u32,
+    pub max_stake: i128,
+    pub max_zeros: u32,
+    pub entropy: BytesN<32>,
+    pub staked_total: i128,
+    pub normalized_total: i128,
 }
 
 #[contracttype]
@@ -24,14 +46,329 @@ pub struct Pail {
     pub zeros: Option<u32>,
 }
 
+// Everything `harvest` needs from a closed block, `reward_pool` is the farm's block reward plus the stake left behind
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockSummary {
+    pub index: u32,
+    pub min_gap: u32,
+    pub min_stake: i128,
+    pub min_zeros: u32,
+    pub max_gap: u32,
+    pub max_stake: i128,
+    pub max_zeros: u32,
+    pub normalized_total: i128,
+    pub reward_pool: i128,
+    pub normalization: Normalization,
+}
+
+// A closed block's summary kept in persistent storage for as long as greenhoused pails still need it
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Greenhouse {
+    pub summary: BlockSummary,
+    pub pails: u32,
+}
+
+// The share of each reward's earnings (reward minus returned stake) locked at harvest, vesting linearly over `ledgers`
+// A `bps` of 0 turns vesting off
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VestingSchedule {
+    pub bps: u32,
+    pub ledgers: u32,
+}
+
+// The protocol's cut of each block's reward, taken out of the reward pool when the block closes
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeSchedule {
+    pub recipient: Address,
+    pub bps: u32,
+}
+
+// Running totals of the asset the farm has moved, `Escrow` farms transfer what `Mint` farms mint and burn
+// `burned` is every stake `plant` took, `minted_stake` and `minted_reward` split what harvests paid out into
+// returned stakes and earnings (vested claims included), `forfeited` is stake left unworked when its block closed
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SupplyStats {
+    pub burned: i128,
+    pub minted_stake: i128,
+    pub minted_reward: i128,
+    pub fees: i128,
+    pub forfeited: i128,
+}
+
+// What the farm signs for as its asset's admin once the homesteader approves, calls on the farm itself are always fine
+// Only `functions` may be called on the asset, `set_admin` only to `successor` and no call may move more than `max_amount`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthPolicy {
+    pub functions: Vec<Symbol>,
+    pub successor: Option<Address>,
+    pub max_amount: i128,
+}
+
+// Once set the homestead is `threshold` of `signers` rather than the homesteader's key, `signers` are
+// ed25519 public keys kept in ascending order
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Multisig {
+    pub signers: Vec<BytesN<32>>,
+    pub threshold: u32,
+}
+
+// One signer's ed25519 signature over the auth payload, a farm's `__check_auth` takes them ordered by `public_key`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub public_key: BytesN<32>,
+    pub signature: BytesN<64>,
+}
+
+// A farm winding down, `plant` opens no block past `final_index`
+// `closed` is the ledger the final block closed on, starting the harvest window, and a `complete` sunset locks the farm
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sunset {
+    pub final_index: u32,
+    pub closed: Option<u32>,
+    pub complete: bool,
+}
+
+// A run of blocks from `start` to `end` whose harvested earnings count towards a `bonus`, shared out once the season is over
+// between the `leaders` farmers with the most contribution, in proportion to it
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Season {
+    pub start: u32,
+    pub end: u32,
+    pub bonus: i128,
+    pub leaders: u32,
+    pub leaderboard: Vec<Standing>,
+}
+
+// A farmer's place on a season's leaderboard, highest `contribution` first
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub farmer: Address,
+    pub contribution: i128,
+    pub claimed: bool,
+}
+
+// A farmer's locked rewards, `locked` releases linearly into `unlocked` from ledger `start` to `end`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vesting {
+    pub unlocked: i128,
+    pub locked: i128,
+    pub start: u32,
+    pub end: u32,
+}
+
+// How the farm takes stakes and pays rewards
+// `Mint` burns and mints so the farm has to be its asset's SAC admin, `Escrow` holds stakes and pays
+// rewards out of a pre-funded treasury so any SEP-41 token can be farmed
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssetMode {
+    Mint,
+    Escrow,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HashAlgorithm {
+    Keccak256,
+    Sha256,
+}
+
+// `reward` includes the returned stake, exactly as `harvest` would mint it
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reward {
+    pub reward: i128,
+    pub normalized_gap: i128,
+    pub normalized_stake: i128,
+    pub normalized_zeros: i128,
+}
+
+// How leading zeros are counted for a block's `zeros` values
+// `Nibbles` is kept so blocks opened before bit counting keep their original scale
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Nibbles,
+    Bits,
+}
+
+// The curve a component is mapped onto before min-max scaling
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+    Linear,
+    Log2,
+    Sqrt,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Normalization {
+    pub gap: Scaling,
+    pub stake: Scaling,
+    pub zeros: Scaling,
+    pub gap_weight: u32,
+    pub stake_weight: u32,
+    pub zeros_weight: u32,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization {
+            gap: Scaling::Linear,
+            stake: Scaling::Linear,
+            zeros: Scaling::Linear,
+            gap_weight: 1,
+            stake_weight: 1,
+            zeros_weight: 1,
+        }
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlgorithmSchedule {
+    pub index: u32,
+    pub algorithm: HashAlgorithm,
+    pub previous: HashAlgorithm,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Storage {
-    Homesteader,        // : address
-    HomesteadAsset,     // : address
-    FarmIndex,          // : u32
-    FarmBlock,          // : Block
-    FarmPaused,         // : bool
-    Block(u32),         // (index) : Block
-    Pail(Address, u32), // (farmer, index) : Pail
+    Homesteader,                  // : address
+    HomesteadAsset,               // : address
+    HomesteadInterval,            // : u64
+    HomesteadReward,              // : i128
+    HomesteadMode,                // : AssetMode
+    HomesteadAlgorithm,           // : AlgorithmSchedule
+    HomesteadNormalization,       // : Normalization
+    HomesteadVesting,             // : VestingSchedule
+    HomesteadFee,                 // : FeeSchedule
+    HomesteadPolicy,              // : AuthPolicy
+    HomesteadSigners,             // : Multisig
+    HomesteadRefunds,             // : Vec<u32>
+    HomesteadSunset,              // : Sunset
+    HomesteadSeasons,             // : u32
+    FarmIndex,                    // : u32 (persistent)
+    FarmBlock,                    // : LegacyBlock (read only)
+    FarmBlockV2,                  // : Block (persistent)
+    FarmPaused,                   // : bool
+    FarmEscrow,                   // : i128 (persistent)
+    FarmSupply,                   // : SupplyStats (persistent)
+    FarmVested,                   // : i128 (persistent)
+    FarmGreenhoused,              // : u32 (persistent)
+    FarmUnclaimed,                // : u32 (persistent)
+    Block(u32),                   // (index) : LegacyBlock (read only)
+    BlockV2(u32),                 // (index) : Block
+    Pail(Address, u32),           // (farmer, index) : Pail
+    BlockSummary(u32),            // (index % summaries) : BlockSummary (persistent)
+    Greenhouse(u32),              // (index) : Greenhouse (persistent)
+    GreenhousePail(Address, u32), // (farmer, index) : Pail (persistent)
+    Vesting(Address),             // (farmer) : Vesting (persistent)
+    Blocked(Address),             // (farmer) : bool (persistent)
+    Season(u32),                  // (season) : Season (persistent)
+    SeasonFarmer(u32, Address),   // (season, farmer) : i128 (persistent)
+}
+
+impl From<HashAlgorithm> for kale_core::HashAlgorithm {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Keccak256 => kale_core::HashAlgorithm::Keccak256,
+            HashAlgorithm::Sha256 => kale_core::HashAlgorithm::Sha256,
+        }
+    }
+}
+
+impl From<Difficulty> for kale_core::Difficulty {
+    fn from(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Nibbles => kale_core::Difficulty::Nibbles,
+            Difficulty::Bits => kale_core::Difficulty::Bits,
+        }
+    }
+}
+
+impl From<Scaling> for kale_core::Scaling {
+    fn from(scaling: Scaling) -> Self {
+        match scaling {
+            Scaling::Linear => kale_core::Scaling::Linear,
+            Scaling::Log2 => kale_core::Scaling::Log2,
+            Scaling::Sqrt => kale_core::Scaling::Sqrt,
+        }
+    }
+}
+
+impl From<&Normalization> for kale_core::Normalization {
+    fn from(normalization: &Normalization) -> Self {
+        kale_core::Normalization {
+            gap: normalization.gap.into(),
+            stake: normalization.stake.into(),
+            zeros: normalization.zeros.into(),
+            gap_weight: normalization.gap_weight,
+            stake_weight: normalization.stake_weight,
+            zeros_weight: normalization.zeros_weight,
+        }
+    }
+}
+
+// Everything a legacy block was opened with was the only option at the time, the pails it holds were never counted
+impl From<LegacyBlock> for Block {
+    fn from(block: LegacyBlock) -> Self {
+        Block {
+            timestamp: block.timestamp,
+            min_gap: block.min_gap,
+            min_stake: block.min_stake,
+            min_zeros: block.min_zeros,
+            max_gap: block.max_gap,
+            max_stake: block.max_stake,
+            max_zeros: block.max_zeros,
+            entropy: block.entropy,
+            staked_total: block.staked_total,
+            normalized_total: block.normalized_total,
+            algorithm: HashAlgorithm::Keccak256,
+            difficulty: Difficulty::Nibbles,
+            normalization: Normalization::default(),
+            pails: u32::MAX,
+            fee: None,
+        }
+    }
+}
+
+impl From<&BlockSummary> for kale_core::Bounds {
+    fn from(summary: &BlockSummary) -> Self {
+        kale_core::Bounds {
+            min_gap: summary.min_gap,
+            min_stake: summary.min_stake,
+            min_zeros: summary.min_zeros,
+            max_gap: summary.max_gap,
+            max_stake: summary.max_stake,
+            max_zeros: summary.max_zeros,
+        }
+    }
+}
+
+impl From<&Block> for kale_core::Bounds {
+    fn from(block: &Block) -> Self {
+        kale_core::Bounds {
+            min_gap: block.min_gap,
+            min_stake: block.min_stake,
+            min_zeros: block.min_zeros,
+            max_gap: block.max_gap,
+            max_stake: block.max_stake,
+            max_zeros: block.max_zeros,
+        }
+    }
 }
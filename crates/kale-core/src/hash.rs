@@ -0,0 +1,77 @@
+pub const PREIMAGE_LENGTH: usize = 76;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HashAlgorithm {
+    Keccak256,
+    Sha256,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Difficulty {
+    Nibbles,
+    Bits,
+}
+
+// `farmer` is the last 32 bytes of the farmer address XDR
+// For G- addresses that's the ed25519 public key, for C- addresses the contract hash
+pub fn generate_preimage(
+    index: u32,
+    nonce: u64,
+    entropy: &[u8; 32],
+    farmer: &[u8; 32],
+) -> [u8; PREIMAGE_LENGTH] {
+    let mut preimage = [0u8; PREIMAGE_LENGTH];
+
+    preimage[..4].copy_from_slice(&index.to_be_bytes());
+    preimage[4..12].copy_from_slice(&nonce.to_be_bytes());
+    preimage[12..44].copy_from_slice(entropy);
+    preimage[44..].copy_from_slice(farmer);
+
+    preimage
+}
+
+// Lets a nonce search reuse one preimage instead of rebuilding it per attempt
+pub fn set_nonce(preimage: &mut [u8; PREIMAGE_LENGTH], nonce: u64) {
+    preimage[4..12].copy_from_slice(&nonce.to_be_bytes());
+}
+
+#[cfg(feature = "hash")]
+pub fn generate_hash(algorithm: HashAlgorithm, preimage: &[u8; PREIMAGE_LENGTH]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+
+    match algorithm {
+        HashAlgorithm::Keccak256 => {
+            use tiny_keccak::{Hasher, Keccak};
+
+            let mut keccak = Keccak::v256();
+            keccak.update(preimage);
+            keccak.finalize(&mut hash);
+        }
+        HashAlgorithm::Sha256 => {
+            use sha2::{Digest, Sha256};
+
+            hash.copy_from_slice(&Sha256::digest(preimage));
+        }
+    }
+
+    hash
+}
+
+pub fn count_zeros(hash: &[u8], difficulty: Difficulty) -> u32 {
+    let mut zeros = 0;
+
+    for byte in hash {
+        if *byte == 0 {
+            zeros += 8;
+        } else {
+            zeros += byte.leading_zeros();
+            break;
+        }
+    }
+
+    match difficulty {
+        Difficulty::Bits => zeros,
+        // Equivalent to counting 2 per zero byte plus `leading_zeros() / 4` for the first non-zero byte
+        Difficulty::Nibbles => zeros / 4,
+    }
+}
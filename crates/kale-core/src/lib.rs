@@ -0,0 +1,18 @@
+#![no_std]
+
+// Everything `work` and `harvest` compute that doesn't need the host
+// The contract depends on this crate so miners and tests running the same code can't drift from it
+
+mod hash;
+mod math;
+mod reward;
+
+pub use hash::*;
+pub use math::*;
+pub use reward::*;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    BoundsInvalid,
+    Overflow,
+}
@@ -0,0 +1,115 @@
+// Fixed point precision for the non-linear curves
+pub const SCALE_BITS: u32 = 24;
+
+// floor(x * y / z), matching `SorobanFixedPoint::fixed_mul_floor` including its 256 bit fallback
+pub fn mul_div_floor(x: i128, y: i128, z: i128) -> Option<i128> {
+    if z == 0 {
+        return None;
+    }
+
+    match x.checked_mul(y) {
+        Some(r) => {
+            if r < 0 || (r > 0 && z < 0) {
+                // ceiling is taken by default for a negative result
+                let remainder = r.checked_rem_euclid(z)?;
+                (r / z).checked_sub(if remainder > 0 { 1 } else { 0 })
+            } else {
+                r.checked_div(z)
+            }
+        }
+        None => {
+            let (high, low) = widening_mul(x.unsigned_abs(), y.unsigned_abs());
+            let (quotient, remainder) = wide_div(high, low, z.unsigned_abs())?;
+
+            // Truncated division, then the same rounding adjustment soroban applies to the I256 result
+            let truncated = if (x < 0) ^ (y < 0) ^ (z < 0) {
+                if quotient > i128::MIN.unsigned_abs() {
+                    return None;
+                }
+
+                (quotient as i128).wrapping_neg()
+            } else {
+                i128::try_from(quotient).ok()?
+            };
+            let product_negative = (x < 0) ^ (y < 0);
+
+            if (product_negative || z < 0) && remainder > 0 {
+                truncated.checked_sub(1)
+            } else {
+                Some(truncated)
+            }
+        }
+    }
+}
+
+// log2(x) with SCALE_BITS fractional bits, x must be >= 1
+pub fn log2(x: u128) -> i128 {
+    let integer = 127 - x.leading_zeros();
+
+    // Normalize x into [1, 2) with 62 fractional bits so squaring can't overflow
+    let mut y = if integer > 62 {
+        x >> (integer - 62)
+    } else {
+        x << (62 - integer)
+    };
+    let mut fraction: i128 = 0;
+
+    for _ in 0..SCALE_BITS {
+        y = (y * y) >> 62;
+        fraction <<= 1;
+
+        if y >= 1 << 63 {
+            y >>= 1;
+            fraction |= 1;
+        }
+    }
+
+    ((integer as i128) << SCALE_BITS) | fraction
+}
+
+// sqrt(x) with SCALE_BITS / 2 fractional bits
+pub fn sqrt(x: u128) -> i128 {
+    (x << SCALE_BITS).isqrt() as i128
+}
+
+fn widening_mul(x: u128, y: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (x_high, x_low) = (x >> 64, x & MASK);
+    let (y_high, y_low) = (y >> 64, y & MASK);
+
+    let low_low = x_low * y_low;
+    let low_high = x_low * y_high;
+    let high_low = x_high * y_low;
+    let high_high = x_high * y_high;
+
+    let middle = (low_low >> 64) + (low_high & MASK) + (high_low & MASK);
+
+    let low = (middle << 64) | (low_low & MASK);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+
+    (high, low)
+}
+
+// Divides the 256 bit (high, low) by divisor, None if the quotient doesn't fit in 128 bits
+fn wide_div(high: u128, low: u128, divisor: u128) -> Option<(u128, u128)> {
+    if high >= divisor {
+        return None;
+    }
+
+    let mut quotient = 0u128;
+    let mut remainder = high;
+
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+
+        remainder = (remainder << 1) | ((low >> i) & 1);
+
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1 << i;
+        }
+    }
+
+    Some((quotient, remainder))
+}
@@ -0,0 +1,132 @@
+use crate::{log2, mul_div_floor, sqrt, Error};
+
+// The curve a component is mapped onto before min-max scaling
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scaling {
+    Linear,
+    Log2,
+    Sqrt,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Normalization {
+    pub gap: Scaling,
+    pub stake: Scaling,
+    pub zeros: Scaling,
+    pub gap_weight: u32,
+    pub stake_weight: u32,
+    pub zeros_weight: u32,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization {
+            gap: Scaling::Linear,
+            stake: Scaling::Linear,
+            zeros: Scaling::Linear,
+            gap_weight: 1,
+            stake_weight: 1,
+            zeros_weight: 1,
+        }
+    }
+}
+
+// A block's min/max values, carried over from the previous block's submissions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bounds {
+    pub min_gap: u32,
+    pub min_stake: i128,
+    pub min_zeros: u32,
+    pub max_gap: u32,
+    pub max_stake: i128,
+    pub max_zeros: u32,
+}
+
+pub fn generate_normalizations(
+    bounds: &Bounds,
+    normalization: &Normalization,
+    gap: u32,
+    stake: i128,
+    zeros: u32,
+) -> Result<(i128, i128, i128), Error> {
+    if bounds.max_gap < bounds.min_gap
+        || bounds.max_stake < bounds.min_stake
+        || bounds.max_zeros < bounds.min_zeros
+    {
+        return Err(Error::BoundsInvalid);
+    }
+
+    // Clamp inputs to valid ranges
+    let gap = gap.max(bounds.min_gap).min(bounds.max_gap);
+    let stake = stake.max(bounds.min_stake).min(bounds.max_stake);
+    let zeros = zeros.max(bounds.min_zeros).min(bounds.max_zeros);
+
+    // Move inputs and bounds onto each component's curve
+    let min_gap = scale(normalization.gap, bounds.min_gap as i128);
+    let max_gap = scale(normalization.gap, bounds.max_gap as i128);
+    let gap = scale(normalization.gap, gap as i128);
+    let min_stake = scale(normalization.stake, bounds.min_stake);
+    let max_stake = scale(normalization.stake, bounds.max_stake);
+    let stake = scale(normalization.stake, stake);
+    let min_zeros = scale(normalization.zeros, bounds.min_zeros as i128);
+    let max_zeros = scale(normalization.zeros, bounds.max_zeros as i128);
+    let zeros = scale(normalization.zeros, zeros as i128);
+
+    // Calculate ranges
+    let range_gap = (max_gap - min_gap).max(1);
+    let range_stake = (max_stake - min_stake).max(1);
+    let range_zeros = (max_zeros - min_zeros).max(1);
+
+    // Find largest range for scaling
+    let max_range = range_gap.max(range_stake).max(range_zeros);
+
+    // Set minimum threshold (1% of max_range)
+    // This is intended to prevent normalization from being too small
+    // Won't disable zero reward claim but will decrease their likelihood
+    let min_threshold = (max_range / 100).max(1);
+
+    // Scale each value relative to max_range, then apply its weight
+    let normalized_gap = mul_div_floor(gap - min_gap, max_range, range_gap)
+        .ok_or(Error::Overflow)?
+        .max(min_threshold)
+        .checked_mul(normalization.gap_weight as i128)
+        .ok_or(Error::Overflow)?;
+    let normalized_stake = mul_div_floor(stake - min_stake, max_range, range_stake)
+        .ok_or(Error::Overflow)?
+        .max(min_threshold)
+        .checked_mul(normalization.stake_weight as i128)
+        .ok_or(Error::Overflow)?;
+    let normalized_zeros = mul_div_floor(zeros - min_zeros, max_range, range_zeros)
+        .ok_or(Error::Overflow)?
+        .max(min_threshold)
+        .checked_mul(normalization.zeros_weight as i128)
+        .ok_or(Error::Overflow)?;
+
+    Ok((normalized_gap, normalized_stake, normalized_zeros))
+}
+
+// A farmer's share of `reward_pool` (`BLOCK_REWARD` + unclaimed stake) plus their returned stake
+pub fn generate_reward(
+    normalizations: (i128, i128, i128),
+    reward_pool: i128,
+    normalized_total: i128,
+    stake: i128,
+) -> Result<i128, Error> {
+    let (normalized_gap, normalized_stake, normalized_zeros) = normalizations;
+
+    mul_div_floor(
+        normalized_gap + normalized_stake + normalized_zeros,
+        reward_pool,
+        normalized_total.max(1),
+    )
+    .and_then(|share| share.checked_add(stake))
+    .ok_or(Error::Overflow)
+}
+
+fn scale(scaling: Scaling, value: i128) -> i128 {
+    match scaling {
+        Scaling::Linear => value,
+        Scaling::Log2 => log2(value as u128 + 1),
+        Scaling::Sqrt => sqrt(value as u128),
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use kale_core::{count_zeros, generate_hash, generate_preimage, set_nonce, Difficulty};
+use serde::Deserialize;
+use stellar_strkey::Strkey;
+
+pub use kale_core::HashAlgorithm;
+
+mod tests;
+
+// Nonces claimed by a thread at a time, small enough that threads notice a find quickly
+const CHUNK_SIZE: u64 = 1 << 16;
+
+// A hash is only 256 bits, asking for more zeros than that would never finish
+pub const MAX_ZEROS: u32 = 256;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    pub index: u32,
+    pub entropy: [u8; 32],
+    pub farmer: String,
+    pub algorithm: HashAlgorithm,
+    pub zeros: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Work {
+    pub nonce: u64,
+    pub hash: [u8; 32],
+    pub zeros: u32,
+}
+
+// What a state file may contain, anything passed as an argument takes precedence
+#[derive(Debug, Default, Deserialize)]
+pub struct State {
+    pub index: Option<u32>,
+    pub entropy: Option<String>,
+    pub farmer: Option<String>,
+    pub algorithm: Option<String>,
+}
+
+impl State {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|error| format!("invalid state file: {error}"))
+    }
+}
+
+pub fn parse_entropy(entropy: &str) -> Result<[u8; 32], String> {
+    hex::decode(entropy.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("entropy must be 32 hex encoded bytes: {entropy}"))
+}
+
+pub fn parse_algorithm(algorithm: &str) -> Result<HashAlgorithm, String> {
+    match algorithm.to_ascii_lowercase().as_str() {
+        "keccak256" => Ok(HashAlgorithm::Keccak256),
+        "sha256" => Ok(HashAlgorithm::Sha256),
+        _ => Err(format!("unknown algorithm: {algorithm}")),
+    }
+}
+
+pub fn parse_zeros(zeros: &str) -> Result<u32, String> {
+    zeros
+        .parse()
+        .ok()
+        .filter(|zeros| *zeros <= MAX_ZEROS)
+        .ok_or_else(|| format!("zeros must be a number of bits up to {MAX_ZEROS}: {zeros}"))
+}
+
+// The same 32 bytes `work` takes from the end of the farmer's XDR
+pub fn farmer_key(farmer: &str) -> Result<[u8; 32], String> {
+    match Strkey::from_string(farmer) {
+        Ok(Strkey::PublicKeyEd25519(public_key)) => Ok(public_key.0),
+        Ok(Strkey::Contract(contract)) => Ok(contract.0),
+        _ => Err(format!("farmer must be a G- or C- address: {farmer}")),
+    }
+}
+
+// Searches nonces across `threads` until a hash with at least `job.zeros` leading zero bits turns up
+// `progress` is called from the calling thread with the running hash count
+pub fn mine(
+    job: &Job,
+    threads: usize,
+    mut progress: impl FnMut(u64, Duration),
+) -> Result<Work, String> {
+    if job.zeros > MAX_ZEROS {
+        return Err(format!(
+            "zeros must be a number of bits up to {MAX_ZEROS}: {}",
+            job.zeros
+        ));
+    }
+
+    let preimage = generate_preimage(job.index, 0, &job.entropy, &farmer_key(&job.farmer)?);
+    let next_chunk = AtomicU64::new(0);
+    let hashes = AtomicU64::new(0);
+    let found = AtomicBool::new(false);
+    let work = Mutex::new(None);
+    let started = Instant::now();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                let mut preimage = preimage;
+
+                while !found.load(Ordering::Relaxed) {
+                    let start = next_chunk.fetch_add(CHUNK_SIZE, Ordering::Relaxed);
+
+                    for nonce in start..start.saturating_add(CHUNK_SIZE) {
+                        set_nonce(&mut preimage, nonce);
+
+                        let hash = generate_hash(job.algorithm, &preimage);
+                        let zeros = count_zeros(&hash, Difficulty::Bits);
+
+                        if zeros >= job.zeros {
+                            found.store(true, Ordering::Relaxed);
+                            work.lock()
+                                .unwrap()
+                                .get_or_insert(Work { nonce, hash, zeros });
+                            break;
+                        }
+                    }
+
+                    hashes.fetch_add(CHUNK_SIZE, Ordering::Relaxed);
+                }
+            });
+        }
+
+        let mut reported = Instant::now();
+
+        while !found.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                progress(hashes.load(Ordering::Relaxed), started.elapsed());
+                reported = Instant::now();
+            }
+        }
+    });
+
+    Ok(work.into_inner().unwrap().unwrap())
+}
@@ -0,0 +1,106 @@
+use std::{env, fs, process, thread};
+
+use kale_miner::{mine, parse_algorithm, parse_entropy, parse_zeros, HashAlgorithm, Job, State};
+
+const USAGE: &str = "\
+Usage: kale-miner --zeros <bits> [options]
+
+Options:
+  --state <file>        JSON file with any of `index`, `entropy`, `farmer` and `algorithm`
+  --index <index>       Current `FarmIndex`
+  --entropy <hex>       Current block entropy
+  --farmer <address>    G- or C- address of the farmer who planted
+  --algorithm <name>    keccak256 (default) or sha256
+  --zeros <bits>        Minimum leading zero bits to search for
+  --threads <count>     Defaults to all available cores";
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut state = State::default();
+    let mut zeros = None;
+    let mut threads = None;
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--state" => {
+                let path = value()?;
+                let json = fs::read_to_string(&path)
+                    .map_err(|error| format!("can't read {path}: {error}"))?;
+                let file = State::from_json(&json)?;
+
+                state = State {
+                    index: state.index.or(file.index),
+                    entropy: state.entropy.or(file.entropy),
+                    farmer: state.farmer.or(file.farmer),
+                    algorithm: state.algorithm.or(file.algorithm),
+                };
+            }
+            "--index" => state.index = Some(parse_number(&value()?)?),
+            "--entropy" => state.entropy = Some(value()?),
+            "--farmer" => state.farmer = Some(value()?),
+            "--algorithm" => state.algorithm = Some(value()?),
+            "--zeros" => zeros = Some(parse_zeros(&value()?)?),
+            "--threads" => threads = Some(parse_number(&value()?)?),
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    let job = Job {
+        index: state.index.ok_or("missing --index")?,
+        entropy: parse_entropy(&state.entropy.ok_or("missing --entropy")?)?,
+        farmer: state.farmer.ok_or("missing --farmer")?,
+        algorithm: match state.algorithm {
+            Some(algorithm) => parse_algorithm(&algorithm)?,
+            None => HashAlgorithm::Keccak256,
+        },
+        zeros: zeros.ok_or("missing --zeros")?,
+    };
+    let threads = threads.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+    });
+
+    eprintln!(
+        "mining index {} for {} on {} threads",
+        job.index, job.farmer, threads
+    );
+
+    let work = mine(&job, threads, |hashes, elapsed| {
+        eprintln!(
+            "{} hashes in {}s ({:.0} H/s)",
+            hashes,
+            elapsed.as_secs(),
+            hashes as f64 / elapsed.as_secs_f64()
+        );
+    })?;
+
+    eprintln!("found {} zeros", work.zeros);
+
+    println!(
+        "--farmer {} --hash {} --nonce {}",
+        job.farmer,
+        hex::encode(work.hash),
+        work.nonce
+    );
+
+    Ok(())
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("not a valid number: {value}"))
+}
@@ -0,0 +1,138 @@
+#![cfg(test)]
+
+use kale_sc::{Contract, ContractClient, BLOCK_INTERVAL, BLOCK_REWARD};
+use soroban_sdk::{
+    testutils::{Address as _, EnvTestConfig, Ledger},
+    token, Address, BytesN, Env,
+};
+use stellar_strkey::{ed25519, Strkey};
+
+use crate::{farmer_key, mine, parse_entropy, parse_zeros, HashAlgorithm, Job, State};
+
+#[test]
+fn test_work_accepts_mined_hashes() {
+    let mut env: Env = Env::default();
+
+    env.set_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+
+    env.ledger().set_min_temp_entry_ttl(17280);
+    env.ledger().set_min_persistent_entry_ttl(2073600);
+
+    env.mock_all_auths();
+
+    let homesteader: Address = Address::generate(&env);
+
+    let asset_sac = env.register_stellar_asset_contract_v2(homesteader.clone());
+    let asset_address = asset_sac.address();
+    let asset_homesteader = token::StellarAssetClient::new(&env, &asset_address);
+
+    let farm_address: Address = env.register(
+        Contract,
+        (
+            &homesteader,
+            &asset_address,
+            BLOCK_INTERVAL,
+            BLOCK_REWARD,
+            kale_sc::types::AssetMode::Mint,
+        ),
+    );
+    let farm_client = ContractClient::new(&env, &farm_address);
+
+    asset_homesteader.set_admin(&farm_address);
+
+    // Miners only know the strkey, cover both address types
+    let g_farmer = Strkey::PublicKeyEd25519(ed25519::PublicKey([7; 32])).to_string();
+    let c_farmer = Address::generate(&env).to_string().to_string();
+
+    farm_client.schedule_algorithm(&2, &kale_sc::types::HashAlgorithm::Sha256);
+
+    let mut entropy = [0u8; 32];
+
+    for (index, algorithm) in [
+        HashAlgorithm::Keccak256,
+        HashAlgorithm::Keccak256,
+        HashAlgorithm::Sha256,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let sequence = env.ledger().sequence();
+
+        for farmer in [&g_farmer, &c_farmer] {
+            farm_client.plant(&Address::from_str(&env, farmer), &0);
+        }
+
+        env.ledger().set_sequence_number(sequence + 1);
+
+        let mut next_entropy = entropy;
+
+        for farmer in [&g_farmer, &c_farmer] {
+            let job = Job {
+                index: index as u32,
+                entropy,
+                farmer: farmer.clone(),
+                algorithm,
+                zeros: 8,
+            };
+            let work = mine(&job, 4, |_, _| {}).unwrap();
+
+            assert!(work.zeros >= 8);
+
+            farm_client.work(
+                &Address::from_str(&env, farmer),
+                &BytesN::from_array(&env, &work.hash),
+                &work.nonce,
+            );
+
+            // The last submitted hash seeds the next block
+            next_entropy = work.hash;
+        }
+
+        entropy = next_entropy;
+
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + BLOCK_INTERVAL);
+    }
+}
+
+#[test]
+fn test_state() {
+    let state = State::from_json(
+        r#"{ "index": 42, "entropy": "0x00000000c0ffee00000000000000000000000000000000000000000000000000" }"#,
+    )
+    .unwrap();
+
+    assert_eq!(state.index, Some(42));
+    assert_eq!(state.farmer, None);
+    assert_eq!(
+        parse_entropy(&state.entropy.unwrap()).unwrap()[4..7],
+        [0xc0, 0xff, 0xee]
+    );
+
+    assert!(parse_entropy("c0ffee").is_err());
+    assert!(State::from_json("{ \"index\": -1 }").is_err());
+    assert!(
+        farmer_key("MA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVAAAAAAAAAAAAAAJM")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_zeros() {
+    assert_eq!(parse_zeros("256"), Ok(256));
+    assert!(parse_zeros("257").is_err());
+    assert!(parse_zeros("-1").is_err());
+
+    // Beyond what a 256 bit hash can have, the threads would never find anything
+    let job = Job {
+        index: 0,
+        entropy: [0; 32],
+        farmer: Strkey::PublicKeyEd25519(ed25519::PublicKey([7; 32])).to_string(),
+        algorithm: HashAlgorithm::Keccak256,
+        zeros: 257,
+    };
+
+    assert!(mine(&job, 1, |_, _| {}).is_err());
+}
@@ -0,0 +1,527 @@
+use std::{fmt::Write, rc::Rc};
+
+use kale_sc::{
+    testutils::FarmHarness,
+    types::{AssetMode, FeeSchedule, Normalization, Scaling},
+    BLOCK_INTERVAL, BLOCK_REWARD,
+};
+use serde::{Deserialize, Serialize};
+use soroban_sdk::{
+    testutils::Address as _,
+    xdr::{LedgerKey, ScVal},
+    Address, Env,
+};
+
+mod tests;
+
+// Ledgers a block stays open for at KALE's own interval, `gap` has to land inside it
+pub const BLOCK_LEDGERS: u32 = BLOCK_INTERVAL as u32 / 5;
+
+// How a group of identical farmers behaves every block
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Strategy {
+    pub name: String,
+    pub count: u32,
+    // Minted once before the first block
+    pub balance: i128,
+    // Share of the current balance planted each block, in basis points
+    pub stake_bps: u32,
+    // Minimum leading zero bits mined each block
+    pub zeros: u32,
+    // Ledgers waited between `plant` and `work`
+    pub gap: u32,
+    // Chance of never calling `work` and forfeiting the stake, in basis points
+    pub miss_bps: u32,
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Strategy {
+            name: String::from("farmer"),
+            count: 1,
+            balance: 0,
+            stake_bps: 0,
+            zeros: 8,
+            gap: 1,
+            miss_bps: 0,
+        }
+    }
+}
+
+// The farm's `Scaling` and `Normalization` as they're written in a config file
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Scaling", rename_all = "snake_case")]
+enum ScalingDef {
+    Linear,
+    Log2,
+    Sqrt,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "Normalization", default = "Normalization::default")]
+struct NormalizationDef {
+    #[serde(with = "ScalingDef")]
+    gap: Scaling,
+    #[serde(with = "ScalingDef")]
+    stake: Scaling,
+    #[serde(with = "ScalingDef")]
+    zeros: Scaling,
+    gap_weight: u32,
+    stake_weight: u32,
+    zeros_weight: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub blocks: u32,
+    pub seed: u64,
+    // The farm's block interval in seconds
+    pub interval: u64,
+    // Minted per block on top of any forfeited stake
+    pub reward: i128,
+    // Share of each block's reward paid out as a fee, in basis points
+    pub fee_bps: u32,
+    // Set on the farm before the first block, the formula change to try out
+    #[serde(with = "NormalizationDef")]
+    pub normalization: Normalization,
+    pub strategies: Vec<Strategy>,
+}
+
+impl Default for Config {
+    // A mixed population of whales, zero stakers and fast, slow and patient hashers
+    fn default() -> Self {
+        let strategy = |name: &str, count, balance, stake_bps, zeros, gap, miss_bps| Strategy {
+            name: String::from(name),
+            count,
+            balance,
+            stake_bps,
+            zeros,
+            gap,
+            miss_bps,
+        };
+
+        Config {
+            blocks: 1000,
+            seed: 0x6b616c65,
+            interval: BLOCK_INTERVAL,
+            reward: BLOCK_REWARD,
+            fee_bps: 0,
+            normalization: Normalization::default(),
+            strategies: vec![
+                strategy("whale", 2, 10000_0000000, 5_000, 8, 10, 0),
+                strategy("zero_staker", 10, 0, 0, 8, 10, 0),
+                strategy("fast_hasher", 4, 10_0000000, 1_000, 14, 10, 0),
+                strategy("slow_hasher", 4, 10_0000000, 1_000, 4, 10, 0),
+                strategy("patient", 4, 10_0000000, 1_000, 8, 50, 500),
+            ],
+        }
+    }
+}
+
+impl Config {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|error| format!("invalid config file: {error}"))
+    }
+
+    // Ledgers a block stays open for, `next_block` moves the ledger along with the clock
+    pub fn block_ledgers(&self) -> u32 {
+        (self.interval / 5).min(u32::MAX as u64) as u32
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.strategies.iter().all(|strategy| strategy.count == 0) {
+            return Err(String::from("config has no farmers"));
+        }
+
+        if self.interval == 0 || self.reward < 0 {
+            return Err(String::from(
+                "interval must be positive and reward can't be negative",
+            ));
+        }
+
+        if self.fee_bps > 10_000 {
+            return Err(String::from("fee_bps can't exceed 10000"));
+        }
+
+        let Normalization {
+            gap_weight,
+            stake_weight,
+            zeros_weight,
+            ..
+        } = self.normalization;
+
+        if gap_weight == 0 && stake_weight == 0 && zeros_weight == 0 {
+            return Err(String::from("normalization needs at least one weight"));
+        }
+
+        let block_ledgers = self.block_ledgers();
+
+        for (index, strategy) in self.strategies.iter().enumerate() {
+            if self.strategies[..index]
+                .iter()
+                .any(|other| other.name == strategy.name)
+            {
+                return Err(format!("{}: strategy names must be unique", strategy.name));
+            }
+
+            if strategy.balance < 0 {
+                return Err(format!("{}: balance can't be negative", strategy.name));
+            }
+
+            if strategy.stake_bps > 10_000 || strategy.miss_bps > 10_000 {
+                return Err(format!("{}: bps can't exceed 10000", strategy.name));
+            }
+
+            if strategy.gap >= block_ledgers {
+                return Err(format!(
+                    "{}: gap must be less than {block_ledgers} ledgers",
+                    strategy.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct FarmerReport {
+    pub farmer: u32,
+    pub strategy: String,
+    pub initial_balance: i128,
+    pub final_balance: i128,
+    // Balance change over the run, harvested minus staked
+    pub earned: i128,
+    pub planted: u32,
+    pub worked: u32,
+    pub staked: i128,
+    pub harvested: i128,
+    pub forfeited: i128,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StrategyReport {
+    pub strategy: String,
+    pub farmers: u32,
+    pub earned: i128,
+    pub mean_earned: i128,
+    // Fraction of everything earned across all farmers
+    pub share: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Report {
+    pub blocks: u32,
+    // Everything `harvest` minted, returned stakes included
+    pub minted: i128,
+    // Everything `plant` burned
+    pub burned: i128,
+    pub forfeited: i128,
+    // Everything paid to the fee recipient
+    pub fees: i128,
+    // Gini coefficient of `earned`, 0 is perfectly even and 1 is winner takes all
+    pub gini: f64,
+    pub strategies: Vec<StrategyReport>,
+    pub farmers: Vec<FarmerReport>,
+}
+
+impl Report {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "farmer,strategy,initial_balance,final_balance,earned,planted,worked,staked,harvested,forfeited\n",
+        );
+
+        for farmer in &self.farmers {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{}",
+                farmer.farmer,
+                farmer.strategy,
+                farmer.initial_balance,
+                farmer.final_balance,
+                farmer.earned,
+                farmer.planted,
+                farmer.worked,
+                farmer.staked,
+                farmer.harvested,
+                farmer.forfeited
+            )
+            .unwrap();
+        }
+
+        csv
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} blocks, {} farmers, {} minted, {} burned, {} forfeited, {} fees, gini {:.4}\n",
+            self.blocks,
+            self.farmers.len(),
+            self.minted,
+            self.burned,
+            self.forfeited,
+            self.fees,
+            self.gini
+        );
+
+        for strategy in &self.strategies {
+            writeln!(
+                summary,
+                "  {:<16} {:>4} farmers {:>20} earned {:>18} mean {:>6.2}% share",
+                strategy.strategy,
+                strategy.farmers,
+                strategy.earned,
+                strategy.mean_earned,
+                strategy.share * 100.0
+            )
+            .unwrap();
+        }
+
+        summary
+    }
+}
+
+// 0 when everyone holds the same, approaching 1 as one holder takes everything
+// Negative values count as nothing held
+pub fn gini(values: &[i128]) -> f64 {
+    let mut values: Vec<f64> = values.iter().map(|value| (*value).max(0) as f64).collect();
+    let total: f64 = values.iter().sum();
+
+    if values.is_empty() || total == 0.0 {
+        return 0.0;
+    }
+
+    values.sort_by(f64::total_cmp);
+
+    let count = values.len() as f64;
+    let weighted: f64 = values
+        .iter()
+        .enumerate()
+        .map(|(rank, value)| (rank + 1) as f64 * value)
+        .sum();
+
+    2.0 * weighted / (count * total) - (count + 1.0) / count
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn chance(&mut self, bps: u32) -> bool {
+        self.next() % 10_000 < bps as u64
+    }
+}
+
+struct Farmer<'a> {
+    address: Address,
+    strategy: &'a Strategy,
+    report: FarmerReport,
+    // The block this farmer has worked and not yet harvested
+    pending: Option<u32>,
+}
+
+// Drives a fresh farm through `config.blocks` blocks, `progress` is called after every block
+pub fn simulate(config: &Config, mut progress: impl FnMut(u32)) -> Result<Report, String> {
+    config.validate()?;
+
+    let harness = FarmHarness::with_config(config.interval, config.reward, AssetMode::Mint);
+
+    // The default budget covers a single transaction, not thousands of blocks of them
+    harness.env.cost_estimate().budget().reset_unlimited();
+
+    harness.farm_client.set_normalization(&config.normalization);
+
+    if config.fee_bps > 0 {
+        harness.farm_client.set_fee(&FeeSchedule {
+            recipient: Address::generate(&harness.env),
+            bps: config.fee_bps,
+        });
+    }
+    let mut rng = XorShift(config.seed.max(1));
+    let mut farmers: Vec<Farmer> = Vec::new();
+
+    for strategy in &config.strategies {
+        for _ in 0..strategy.count {
+            farmers.push(Farmer {
+                address: harness.farmer(strategy.balance),
+                strategy,
+                report: FarmerReport {
+                    farmer: farmers.len() as u32,
+                    strategy: strategy.name.clone(),
+                    initial_balance: strategy.balance,
+                    ..Default::default()
+                },
+                pending: None,
+            });
+        }
+    }
+
+    // Work in gap order so every farmer's gap is exactly what their strategy asked for
+    let mut order: Vec<usize> = (0..farmers.len()).collect();
+    order.sort_by_key(|farmer| farmers[*farmer].strategy.gap);
+
+    for block in 0..config.blocks {
+        if block > 0 {
+            harness.next_block();
+        }
+
+        for farmer in farmers.iter_mut() {
+            let stake =
+                harness.balance(&farmer.address) * farmer.strategy.stake_bps as i128 / 10_000;
+
+            harness.plant(&farmer.address, stake);
+
+            farmer.report.planted += 1;
+            farmer.report.staked += stake;
+        }
+
+        let index = harness.index();
+
+        // The block the farmers just planted into closed the one they worked before
+        for farmer in farmers.iter_mut() {
+            if let Some(pending) = farmer.pending.take() {
+                farmer.report.harvested += harness.harvest(&farmer.address, pending);
+            }
+        }
+
+        let mut gap = 0;
+
+        for farmer in &order {
+            let farmer = &mut farmers[*farmer];
+            let stake = harness.pail(&farmer.address, index).unwrap().stake;
+
+            if rng.chance(farmer.strategy.miss_bps) {
+                farmer.report.forfeited += stake;
+                continue;
+            }
+
+            harness.advance_ledgers(farmer.strategy.gap - gap);
+            gap = farmer.strategy.gap;
+
+            harness.work(&farmer.address, farmer.strategy.zeros);
+
+            farmer.report.worked += 1;
+            farmer.pending = Some(index);
+        }
+
+        compact_storage(&harness.env);
+
+        progress(block);
+    }
+
+    // Harvest the final block so nothing earned is left behind
+    if config.blocks > 0 {
+        harness.next_block();
+        harness.plant(&harness.farmer(0), 0);
+
+        for farmer in farmers.iter_mut() {
+            if let Some(pending) = farmer.pending.take() {
+                farmer.report.harvested += harness.harvest(&farmer.address, pending);
+            }
+        }
+    }
+
+    let farmers: Vec<FarmerReport> = farmers
+        .into_iter()
+        .map(|farmer| {
+            let final_balance = harness.balance(&farmer.address);
+
+            FarmerReport {
+                final_balance,
+                earned: final_balance - farmer.report.initial_balance,
+                ..farmer.report
+            }
+        })
+        .collect();
+
+    Ok(report(config, farmers, harness.farm_client.fees()))
+}
+
+// The test host never forgets a key, spent auth nonces and deleted entries like harvested pails
+// pile up and every call copies them, so long runs compact after every block
+// This reaches into soroban-env-host's storage, which isn't a stable API, so it stays private to the simulator
+// Only safe on envs that didn't start from a ledger snapshot, which would resurrect deleted entries
+fn compact_storage(env: &Env) {
+    let host = env.host();
+    let budget = host.budget_cloned();
+
+    host.with_mut_storage(|storage| {
+        let stale: Vec<_> = storage
+            .map
+            .iter(&budget)?
+            .filter(|(key, entry)| {
+                entry.is_none()
+                    || matches!(
+                        key.as_ref(),
+                        LedgerKey::ContractData(data)
+                            if matches!(data.key, ScVal::LedgerKeyNonce(_))
+                    )
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in stale {
+            if let Some((map, _)) = storage.map.remove::<Rc<LedgerKey>>(&key, &budget)? {
+                storage.map = map;
+            }
+        }
+
+        storage.footprint = Default::default();
+
+        Ok(())
+    })
+    .unwrap();
+}
+
+fn report(config: &Config, farmers: Vec<FarmerReport>, fees: i128) -> Report {
+    let earned: Vec<i128> = farmers.iter().map(|farmer| farmer.earned).collect();
+    let total = earned.iter().map(|earned| (*earned).max(0)).sum::<i128>();
+
+    let strategies = config
+        .strategies
+        .iter()
+        .filter(|strategy| strategy.count > 0)
+        .map(|strategy| {
+            let earned = farmers
+                .iter()
+                .filter(|farmer| farmer.strategy == strategy.name)
+                .map(|farmer| farmer.earned)
+                .sum::<i128>();
+
+            StrategyReport {
+                strategy: strategy.name.clone(),
+                farmers: strategy.count,
+                earned,
+                mean_earned: earned / strategy.count as i128,
+                share: if total > 0 {
+                    earned.max(0) as f64 / total as f64
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+
+    Report {
+        blocks: config.blocks,
+        minted: farmers.iter().map(|farmer| farmer.harvested).sum(),
+        burned: farmers.iter().map(|farmer| farmer.staked).sum(),
+        forfeited: farmers.iter().map(|farmer| farmer.forfeited).sum(),
+        fees,
+        gini: gini(&earned),
+        strategies,
+        farmers,
+    }
+}
@@ -0,0 +1,86 @@
+use std::{env, fs, process};
+
+use kale_sim::{simulate, Config};
+
+const USAGE: &str = "\
+Usage: kale-sim [options]
+
+Options:
+  --config <file>       JSON file with `blocks`, `seed`, `strategies` and the farm's `interval`, `reward`,
+                        `fee_bps` and `normalization`, defaults to a mixed population on KALE's settings
+  --blocks <count>      Overrides the config's block count
+  --seed <seed>         Overrides the config's seed
+  --format <format>     csv (default) or json, the per-farmer report written to stdout
+  --out <file>          Write the report here instead of stdout";
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut config = Config::default();
+    let mut blocks = None;
+    let mut seed = None;
+    let mut format = String::from("csv");
+    let mut out = None;
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--config" => {
+                let path = value()?;
+                let json = fs::read_to_string(&path)
+                    .map_err(|error| format!("can't read {path}: {error}"))?;
+
+                config = Config::from_json(&json)?;
+            }
+            "--blocks" => blocks = Some(parse_number(&value()?)?),
+            "--seed" => seed = Some(parse_number(&value()?)?),
+            "--format" => format = value()?.to_ascii_lowercase(),
+            "--out" => out = Some(value()?),
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    config.blocks = blocks.unwrap_or(config.blocks);
+    config.seed = seed.unwrap_or(config.seed);
+
+    if format != "csv" && format != "json" {
+        return Err(format!("unknown format: {format}"));
+    }
+
+    let report = simulate(&config, |block| {
+        if (block + 1) % 100 == 0 {
+            eprintln!("{} of {} blocks", block + 1, config.blocks);
+        }
+    })?;
+    let output = match format.as_str() {
+        "json" => report.to_json(),
+        _ => report.to_csv(),
+    };
+
+    match out {
+        Some(path) => {
+            fs::write(&path, output).map_err(|error| format!("can't write {path}: {error}"))?
+        }
+        None => print!("{output}"),
+    }
+
+    eprint!("{}", report.summary());
+
+    Ok(())
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("not a valid number: {value}"))
+}
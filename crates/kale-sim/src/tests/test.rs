@@ -0,0 +1,237 @@
+#![cfg(test)]
+
+use kale_sc::types::{Normalization, Scaling};
+
+use crate::{gini, simulate, Config, Strategy, BLOCK_LEDGERS};
+
+fn config(blocks: u32, strategies: Vec<Strategy>) -> Config {
+    Config {
+        blocks,
+        seed: 1,
+        strategies,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_gini() {
+    assert_eq!(gini(&[]), 0.0);
+    assert_eq!(gini(&[0, 0]), 0.0);
+    assert_eq!(gini(&[5, 5, 5, 5]), 0.0);
+    assert_eq!(gini(&[0, 0, 0, 10]), 0.75);
+    assert_eq!(gini(&[-10, 0, 0, 10]), 0.75);
+    assert!((gini(&[1, 2, 3, 4]) - 0.25).abs() < 1e-12);
+}
+
+#[test]
+fn test_simulate() {
+    let config = config(
+        6,
+        vec![
+            Strategy {
+                name: String::from("whale"),
+                balance: 1000_0000000,
+                stake_bps: 5_000,
+                ..Default::default()
+            },
+            Strategy {
+                name: String::from("zero_staker"),
+                count: 3,
+                zeros: 4,
+                gap: 20,
+                ..Default::default()
+            },
+            Strategy {
+                name: String::from("flaky"),
+                balance: 10_0000000,
+                stake_bps: 10_000,
+                miss_bps: 10_000,
+                ..Default::default()
+            },
+        ],
+    );
+    let report = simulate(&config, |_| {}).unwrap();
+
+    assert_eq!(report.farmers.len(), 5);
+    assert_eq!(report.strategies.len(), 3);
+
+    for farmer in &report.farmers {
+        assert_eq!(farmer.planted, 6);
+        assert_eq!(
+            farmer.final_balance - farmer.initial_balance,
+            farmer.harvested - farmer.staked
+        );
+    }
+
+    // Missed every `work` so the whole balance went into the first block's pool
+    let flaky = &report.farmers[4];
+    assert_eq!(flaky.worked, 0);
+    assert_eq!(flaky.forfeited, 10_0000000);
+    assert_eq!(flaky.final_balance, 0);
+    assert_eq!(report.forfeited, 10_0000000);
+
+    for farmer in &report.farmers[..4] {
+        assert_eq!(farmer.worked, 6);
+        assert!(farmer.earned > 0);
+    }
+
+    assert_eq!(
+        report.minted - report.burned,
+        report.farmers.iter().map(|f| f.earned).sum::<i128>()
+    );
+    assert!(report.gini > 0.0 && report.gini < 1.0);
+    assert_eq!(
+        report
+            .strategies
+            .iter()
+            .map(|s| s.share)
+            .sum::<f64>()
+            .round(),
+        1.0
+    );
+
+    let csv = report.to_csv();
+    assert_eq!(csv.lines().count(), 6);
+    assert!(csv
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("0,whale,10000000000,"));
+
+    // The same seed replays the same run
+    assert_eq!(simulate(&config, |_| {}).unwrap(), report);
+}
+
+#[test]
+fn test_config() {
+    let config = Config::from_json(
+        r#"{ "blocks": 10, "strategies": [{ "name": "whale", "count": 2, "balance": 100, "stake_bps": 5000 }] }"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.blocks, 10);
+    assert_eq!(config.strategies[0].count, 2);
+    assert_eq!(config.strategies[0].zeros, Strategy::default().zeros);
+    assert!(config.validate().is_ok());
+    assert!(Config::default().validate().is_ok());
+
+    let invalid = [
+        config.clone().with(|strategy| strategy.gap = BLOCK_LEDGERS),
+        config.clone().with(|strategy| strategy.stake_bps = 10_001),
+        config.clone().with(|strategy| strategy.balance = -1),
+        config.clone().with(|strategy| strategy.count = 0),
+    ];
+
+    for config in invalid {
+        assert!(config.validate().is_err());
+        assert!(simulate(&config, |_| {}).is_err());
+    }
+
+    let mut duplicate = config.clone();
+    duplicate.strategies.push(duplicate.strategies[0].clone());
+    assert!(duplicate.validate().is_err());
+
+    // The farm's settings default to KALE's, a partial normalization keeps the rest of the defaults
+    let config = Config::from_json(
+        r#"{ "interval": 60, "reward": 100, "fee_bps": 500, "normalization": { "stake": "log2", "zeros_weight": 3 }, "strategies": [{ "gap": 11 }] }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        (config.interval, config.reward, config.fee_bps),
+        (60, 100, 500)
+    );
+    assert_eq!(
+        config.normalization,
+        Normalization {
+            stake: Scaling::Log2,
+            zeros_weight: 3,
+            ..Default::default()
+        }
+    );
+    assert_eq!(config.block_ledgers(), 12);
+    assert!(config.validate().is_ok());
+
+    let invalid = [
+        Config {
+            normalization: Normalization {
+                gap_weight: 0,
+                stake_weight: 0,
+                zeros_weight: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Config {
+            fee_bps: 10_001,
+            ..Default::default()
+        },
+        // Strategies' gaps have to fit the configured interval rather than KALE's
+        Config {
+            interval: 60,
+            ..Default::default()
+        },
+    ];
+
+    for config in invalid {
+        assert!(config.validate().is_err());
+    }
+}
+
+#[test]
+fn test_simulate_formula() {
+    let strategies = vec![
+        Strategy {
+            name: String::from("whale"),
+            balance: 1000_0000000,
+            stake_bps: 5_000,
+            ..Default::default()
+        },
+        Strategy {
+            name: String::from("hasher"),
+            zeros: 12,
+            ..Default::default()
+        },
+    ];
+    let baseline = simulate(&config(4, strategies.clone()), |_| {}).unwrap();
+
+    // Only counting zeros hands the hasher a bigger share of the same reward than the default formula does
+    let zeros_only = Config {
+        normalization: Normalization {
+            gap_weight: 0,
+            stake_weight: 0,
+            ..Default::default()
+        },
+        ..config(4, strategies.clone())
+    };
+    let report = simulate(&zeros_only, |_| {}).unwrap();
+
+    assert!(report.strategies[1].share > baseline.strategies[1].share);
+    assert_eq!(report.fees, 0);
+
+    // A shorter interval with a smaller reward and a fee mints less and pays the rest as fees
+    let farm = Config {
+        interval: 60,
+        reward: 100_0000000,
+        fee_bps: 1_000,
+        ..config(4, strategies)
+    };
+    let report = simulate(&farm, |_| {}).unwrap();
+
+    // Shares round down, so a few stroops of each block's pool are never paid out
+    let earned = report.minted - report.burned;
+
+    assert_eq!(report.fees, 4 * 10_0000000);
+    assert!(earned <= 4 * 90_0000000 && earned > 4 * 90_0000000 - 10);
+}
+
+trait With {
+    fn with(self, update: impl Fn(&mut Strategy)) -> Self;
+}
+
+impl With for Config {
+    fn with(mut self, update: impl Fn(&mut Strategy)) -> Self {
+        update(&mut self.strategies[0]);
+        self
+    }
+}